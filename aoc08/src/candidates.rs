@@ -0,0 +1,177 @@
+use std::fmt::Display;
+
+use crate::{calculate_scenic_score, viewing_distance, Direction};
+
+/// Restrictions on where a tree house may be built.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) struct Constraints {
+    /// Minimum number of trees between the candidate and the closest edge.
+    pub(crate) min_edge_distance: usize,
+    /// Minimum height of the candidate tree itself.
+    pub(crate) min_height: u8,
+}
+
+impl Constraints {
+    fn allows(&self, map: &[Vec<u8>], row: usize, column: usize) -> bool {
+        let edge_distance = row
+            .min(column)
+            .min(map.len() - 1 - row)
+            .min(map[0].len() - 1 - column);
+        edge_distance >= self.min_edge_distance && map[row][column] >= self.min_height
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Candidate {
+    pub(crate) row: usize,
+    pub(crate) column: usize,
+    pub(crate) height: u8,
+    /// Viewing distances in the order of `Direction::ALL`.
+    pub(crate) viewing_distances: [usize; 4],
+    pub(crate) score: usize,
+}
+
+impl Candidate {
+    fn new(map: &[Vec<u8>], row: usize, column: usize) -> Self {
+        Self {
+            row,
+            column,
+            height: map[row][column],
+            viewing_distances: Direction::ALL
+                .map(|direction| viewing_distance(map, row, column, direction)),
+            score: calculate_scenic_score(map, row, column),
+        }
+    }
+}
+
+impl Display for Candidate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let distances: Vec<_> = Direction::ALL
+            .iter()
+            .zip(self.viewing_distances)
+            .map(|(direction, distance)| format!("{direction} {distance}"))
+            .collect();
+        f.write_fmt(format_args!(
+            "row {}, column {} (height {}): score {} ({})",
+            self.row,
+            self.column,
+            self.height,
+            self.score,
+            distances.join(", "),
+        ))
+    }
+}
+
+/// Returns the `k` trees with the highest scenic scores that satisfy the
+/// constraints, best first. Ties are broken by row and then by column, so the
+/// result does not depend on iteration order.
+#[must_use]
+pub(crate) fn top_candidates(
+    map: &[Vec<u8>],
+    k: usize,
+    constraints: &Constraints,
+) -> Vec<Candidate> {
+    let mut candidates: Vec<_> = (0..map.len())
+        .flat_map(|row| (0..map[row].len()).map(move |column| (row, column)))
+        .filter(|(row, column)| constraints.allows(map, *row, *column))
+        .map(|(row, column)| Candidate::new(map, row, column))
+        .collect();
+    candidates.sort_by(|left, right| {
+        right
+            .score
+            .cmp(&left.score)
+            .then(left.row.cmp(&right.row))
+            .then(left.column.cmp(&right.column))
+    });
+    candidates.truncate(k);
+    candidates
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{top_candidates, Constraints};
+    use crate::tests::example_map;
+
+    /// Returns the position and score of the top `k` candidates.
+    fn ranking(k: usize, constraints: Constraints) -> Vec<((usize, usize), usize)> {
+        top_candidates(&example_map(), k, &constraints)
+            .into_iter()
+            .map(|candidate| ((candidate.row, candidate.column), candidate.score))
+            .collect()
+    }
+
+    #[test]
+    fn best_trees_of_the_example() {
+        assert_eq!(
+            ranking(3, Constraints::default()),
+            [((3, 2), 8), ((2, 1), 6), ((1, 2), 4)]
+        );
+        let candidate = &top_candidates(&example_map(), 1, &Constraints::default())[0];
+        assert_eq!(candidate.height, 5);
+        assert_eq!(candidate.viewing_distances, [2, 2, 1, 2]);
+        assert_eq!(
+            candidate.to_string(),
+            "row 3, column 2 (height 5): score 8 (up 2, left 2, down 1, right 2)"
+        );
+        assert_eq!(ranking(0, Constraints::default()), []);
+        assert_eq!(ranking(100, Constraints::default()).len(), 25);
+    }
+
+    #[test]
+    fn ties_are_broken_by_row_and_column() {
+        assert_eq!(
+            ranking(11, Constraints::default())[5..],
+            [
+                ((1, 1), 1),
+                ((1, 3), 1),
+                ((2, 2), 1),
+                ((3, 1), 1),
+                ((0, 0), 0),
+                ((0, 1), 0),
+            ]
+        );
+    }
+
+    #[test]
+    fn constraints() {
+        let min_height = Constraints {
+            min_height: 5,
+            ..Constraints::default()
+        };
+        assert_eq!(
+            ranking(6, min_height),
+            [
+                ((3, 2), 8),
+                ((2, 1), 6),
+                ((1, 2), 4),
+                ((1, 1), 1),
+                ((0, 3), 0),
+                ((2, 0), 0),
+            ]
+        );
+        let min_edge_distance = Constraints {
+            min_edge_distance: 2,
+            ..Constraints::default()
+        };
+        assert_eq!(ranking(3, min_edge_distance), [((2, 2), 1)]);
+        let both = Constraints {
+            min_edge_distance: 1,
+            min_height: 4,
+        };
+        assert_eq!(
+            ranking(10, both),
+            [
+                ((3, 2), 8),
+                ((2, 1), 6),
+                ((1, 2), 4),
+                ((3, 3), 3),
+                ((1, 1), 1)
+            ]
+        );
+        let impossible = Constraints {
+            min_edge_distance: 3,
+            min_height: 0,
+        };
+        assert_eq!(ranking(3, impossible), []);
+    }
+}
@@ -0,0 +1,198 @@
+use std::fmt::Display;
+
+use crate::{calculate_scenic_score, trees_in_direction, Direction};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct Tree {
+    pub(crate) row: usize,
+    pub(crate) column: usize,
+    pub(crate) height: u8,
+}
+
+impl Display for Tree {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_fmt(format_args!(
+            "({}, {}) of height {}",
+            self.row, self.column, self.height
+        ))
+    }
+}
+
+/// What ends the view in one direction.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum ViewEnd {
+    /// A tree that is at least as tall as the viewing tree.
+    BlockedBy(Tree),
+    EdgeReached,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct DirectionExplanation {
+    pub(crate) direction: Direction,
+    /// The shorter trees that can be seen, nearest first. A blocking tree is
+    /// seen as well, but is stored in `end` instead.
+    pub(crate) trees_seen: Vec<Tree>,
+    pub(crate) end: ViewEnd,
+}
+
+impl DirectionExplanation {
+    /// The viewing distance in this direction, which is the factor it
+    /// contributes to the scenic score.
+    pub fn factor(&self) -> usize {
+        self.trees_seen.len() + usize::from(matches!(self.end, ViewEnd::BlockedBy(_)))
+    }
+}
+
+impl Display for DirectionExplanation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let trees_seen: Vec<_> = self.trees_seen.iter().map(Tree::to_string).collect();
+        f.write_fmt(format_args!("{}: ", self.direction))?;
+        if trees_seen.is_empty() {
+            f.write_str("sees no shorter trees")?;
+        } else {
+            f.write_fmt(format_args!("sees {}", trees_seen.join(", ")))?;
+        }
+        match self.end {
+            ViewEnd::BlockedBy(tree) => f.write_fmt(format_args!(", blocked by {tree}"))?,
+            ViewEnd::EdgeReached => f.write_str(", reaches the edge")?,
+        }
+        f.write_fmt(format_args!(" => factor {}", self.factor()))
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Explanation {
+    pub(crate) tree: Tree,
+    /// One entry per direction, in the order of `Direction::ALL`.
+    pub(crate) directions: Vec<DirectionExplanation>,
+    pub(crate) scenic_score: usize,
+}
+
+impl Display for Explanation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_fmt(format_args!(
+            "tree {} has a scenic score of {}",
+            self.tree, self.scenic_score
+        ))?;
+        for direction in &self.directions {
+            f.write_fmt(format_args!("\n  {direction}"))?;
+        }
+        let factors: Vec<_> = self
+            .directions
+            .iter()
+            .map(|direction| direction.factor().to_string())
+            .collect();
+        f.write_fmt(format_args!(
+            "\n  {} = {}",
+            factors.join(" * "),
+            self.scenic_score
+        ))
+    }
+}
+
+/// Lists, for every direction, which trees the tree at `(row, column)` sees and
+/// what ends its view.
+#[must_use]
+pub(crate) fn explain(map: &[Vec<u8>], row: usize, column: usize) -> Explanation {
+    let height = map[row][column];
+    let directions = Direction::ALL
+        .iter()
+        .map(|direction| {
+            let mut trees_seen = Vec::new();
+            let mut end = ViewEnd::EdgeReached;
+            for (current_row, current_column) in trees_in_direction(map, row, column, *direction) {
+                let tree = Tree {
+                    row: current_row,
+                    column: current_column,
+                    height: map[current_row][current_column],
+                };
+                if tree.height >= height {
+                    end = ViewEnd::BlockedBy(tree);
+                    break;
+                }
+                trees_seen.push(tree);
+            }
+            DirectionExplanation {
+                direction: *direction,
+                trees_seen,
+                end,
+            }
+        })
+        .collect();
+    Explanation {
+        tree: Tree {
+            row,
+            column,
+            height,
+        },
+        directions,
+        scenic_score: calculate_scenic_score(map, row, column),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{explain, DirectionExplanation, Tree, ViewEnd};
+    use crate::{calculate_scenic_score, tests::example_map, Direction};
+
+    fn tree(row: usize, column: usize, height: u8) -> Tree {
+        Tree {
+            row,
+            column,
+            height,
+        }
+    }
+
+    #[test]
+    fn best_tree_of_the_example() {
+        let explanation = explain(&example_map(), 3, 2);
+        assert_eq!(explanation.tree, tree(3, 2, 5));
+        assert_eq!(explanation.scenic_score, 8);
+        assert_eq!(
+            explanation.directions,
+            [
+                DirectionExplanation {
+                    direction: Direction::Up,
+                    trees_seen: vec![tree(2, 2, 3)],
+                    end: ViewEnd::BlockedBy(tree(1, 2, 5)),
+                },
+                DirectionExplanation {
+                    direction: Direction::Left,
+                    trees_seen: vec![tree(3, 1, 3), tree(3, 0, 3)],
+                    end: ViewEnd::EdgeReached,
+                },
+                DirectionExplanation {
+                    direction: Direction::Down,
+                    trees_seen: vec![tree(4, 2, 3)],
+                    end: ViewEnd::EdgeReached,
+                },
+                DirectionExplanation {
+                    direction: Direction::Right,
+                    trees_seen: vec![tree(3, 3, 4)],
+                    end: ViewEnd::BlockedBy(tree(3, 4, 9)),
+                },
+            ]
+        );
+        assert_eq!(
+            explanation.to_string().lines().last(),
+            Some("  2 * 2 * 1 * 2 = 8")
+        );
+    }
+
+    #[test]
+    fn factors_multiply_to_the_scenic_score() {
+        let map = example_map();
+        for row in 0..map.len() {
+            for column in 0..map[row].len() {
+                let explanation = explain(&map, row, column);
+                let product: usize = explanation
+                    .directions
+                    .iter()
+                    .map(DirectionExplanation::factor)
+                    .product();
+                assert_eq!(product, calculate_scenic_score(&map, row, column));
+                assert_eq!(product, explanation.scenic_score);
+            }
+        }
+    }
+}
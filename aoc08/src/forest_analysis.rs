@@ -0,0 +1,129 @@
+use crate::{calculate_scenic_score, is_visible, parallel};
+
+/// Visibility and scenic scores of every tree, kept up to date while the
+/// heights of individual trees are changed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct ForestAnalysis {
+    map: Vec<Vec<u8>>,
+    visibility: Vec<Vec<bool>>,
+    scenic_scores: Vec<Vec<usize>>,
+}
+
+impl ForestAnalysis {
+    pub fn new(map: Vec<Vec<u8>>) -> Self {
+        let num_threads = parallel::default_num_threads();
+        let visibility = parallel::analyze_cells(&map, num_threads, is_visible);
+        let scenic_scores = parallel::analyze_cells(&map, num_threads, calculate_scenic_score);
+        Self {
+            map,
+            visibility,
+            scenic_scores,
+        }
+    }
+
+    pub fn map(&self) -> &[Vec<u8>] {
+        &self.map
+    }
+
+    pub fn is_visible(&self, row: usize, column: usize) -> bool {
+        self.visibility[row][column]
+    }
+
+    pub fn scenic_score(&self, row: usize, column: usize) -> usize {
+        self.scenic_scores[row][column]
+    }
+
+    pub fn visible_count(&self) -> usize {
+        self.visibility
+            .iter()
+            .flatten()
+            .filter(|visible| **visible)
+            .count()
+    }
+
+    pub fn max_scenic_score(&self) -> usize {
+        self.scenic_scores
+            .iter()
+            .flatten()
+            .copied()
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Changes the height of a single tree. Visibility and scenic score of a
+    /// tree only depend on the trees in its own row and column, so only the
+    /// row and the column of the changed tree are recomputed.
+    pub fn set_height(&mut self, row: usize, column: usize, height: u8) {
+        if self.map[row][column] == height {
+            return;
+        }
+        self.map[row][column] = height;
+        let affected_cells = (0..self.map[row].len())
+            .map(|current_column| (row, current_column))
+            .chain((0..self.map.len()).map(|current_row| (current_row, column)));
+        for (current_row, current_column) in affected_cells {
+            self.visibility[current_row][current_column] =
+                is_visible(&self.map, current_row, current_column);
+            self.scenic_scores[current_row][current_column] =
+                calculate_scenic_score(&self.map, current_row, current_column);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ForestAnalysis;
+    use crate::{
+        generator::{generate, Distribution},
+        tests::example_map,
+    };
+
+    /// Changes the given trees one after another and compares the analysis
+    /// with one computed from scratch after every change.
+    fn check_updates(map: Vec<Vec<u8>>, changes: &[(usize, usize, u8)]) {
+        let mut analysis = ForestAnalysis::new(map);
+        for (row, column, height) in changes.iter().copied() {
+            analysis.set_height(row, column, height);
+            assert_eq!(analysis.map()[row][column], height);
+            assert!(analysis == ForestAnalysis::new(analysis.map().to_vec()));
+        }
+    }
+
+    #[test]
+    fn updates_on_the_example_forest() {
+        check_updates(
+            example_map(),
+            &[
+                // the best tree, corners, edges and an unchanged height
+                (3, 2, 0),
+                (0, 0, 9),
+                (4, 4, 0),
+                (0, 2, 1),
+                (2, 4, 9),
+                (4, 1, 5),
+                (1, 1, 5),
+                (2, 2, 9),
+                (3, 2, 5),
+            ],
+        );
+    }
+
+    #[test]
+    fn updates_on_a_generated_forest() {
+        let map = generate(30, 20, Distribution::SmoothTerrain, 3);
+        check_updates(
+            map,
+            &[
+                (0, 0, 0),
+                (19, 29, 9),
+                (0, 15, 9),
+                (10, 0, 9),
+                (19, 7, 0),
+                (10, 29, 0),
+                (10, 15, 9),
+                (10, 15, 0),
+                (5, 5, 4),
+            ],
+        );
+    }
+}
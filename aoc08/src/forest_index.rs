@@ -0,0 +1,144 @@
+use crate::Direction;
+
+/// Precomputed tables that answer visibility and viewing distance queries for
+/// single trees in constant time.
+///
+/// For every tree and direction the index stores the height of the tallest
+/// tree on the line of sight (the prefix or suffix maximum of its row or
+/// column) and the distance to the next tree that is at least as tall (or to
+/// the edge). Both tables are built with one sweep per row and column.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct ForestIndex {
+    map: Vec<Vec<u8>>,
+    /// `max_heights[direction][row][column]`, -1 if there are no trees in
+    /// that direction.
+    max_heights: [Vec<Vec<i8>>; 4],
+    /// `viewing_distances[direction][row][column]`
+    viewing_distances: [Vec<Vec<usize>>; 4],
+}
+
+impl ForestIndex {
+    pub fn new(map: &[Vec<u8>]) -> Self {
+        let map_height = map.len();
+        let map_width = map[0].len();
+        let mut max_heights = std::array::from_fn(|_| vec![vec![-1; map_width]; map_height]);
+        let mut viewing_distances = std::array::from_fn(|_| vec![vec![0; map_width]; map_height]);
+        for direction in Direction::ALL {
+            // every line is walked starting at the edge the direction points
+            // to, so the trees "in direction" of a cell have already been seen
+            let lines: Vec<Vec<(usize, usize)>> = match direction {
+                Direction::Up => (0..map_width)
+                    .map(|column| (0..map_height).map(|row| (row, column)).collect())
+                    .collect(),
+                Direction::Down => (0..map_width)
+                    .map(|column| (0..map_height).rev().map(|row| (row, column)).collect())
+                    .collect(),
+                Direction::Left => (0..map_height)
+                    .map(|row| (0..map_width).map(|column| (row, column)).collect())
+                    .collect(),
+                Direction::Right => (0..map_height)
+                    .map(|row| (0..map_width).rev().map(|column| (row, column)).collect())
+                    .collect(),
+            };
+            let max_heights = &mut max_heights[direction as usize];
+            let viewing_distances = &mut viewing_distances[direction as usize];
+            for line in lines {
+                let mut max_height = -1;
+                // positions along the line of the trees that may still block
+                // the view of later trees, with strictly decreasing heights
+                let mut blockers: Vec<usize> = Vec::new();
+                for (position, (row, column)) in line.iter().copied().enumerate() {
+                    let height = map[row][column];
+                    max_heights[row][column] = max_height;
+                    max_height = max_height.max(height as i8);
+                    while blockers.last().is_some_and(|blocker| {
+                        let (blocker_row, blocker_column) = line[*blocker];
+                        map[blocker_row][blocker_column] < height
+                    }) {
+                        blockers.pop();
+                    }
+                    viewing_distances[row][column] = match blockers.last() {
+                        Some(blocker) => position - blocker,
+                        None => position,
+                    };
+                    blockers.push(position);
+                }
+            }
+        }
+        Self {
+            map: map.to_vec(),
+            max_heights,
+            viewing_distances,
+        }
+    }
+
+    pub fn is_visible_from(&self, row: usize, column: usize, direction: Direction) -> bool {
+        self.max_heights[direction as usize][row][column] < self.map[row][column] as i8
+    }
+
+    pub fn is_visible(&self, row: usize, column: usize) -> bool {
+        Direction::ALL
+            .iter()
+            .any(|direction| self.is_visible_from(row, column, *direction))
+    }
+
+    pub fn viewing_distance(&self, row: usize, column: usize, direction: Direction) -> usize {
+        self.viewing_distances[direction as usize][row][column]
+    }
+
+    pub fn scenic_score(&self, row: usize, column: usize) -> usize {
+        Direction::ALL
+            .iter()
+            .map(|direction| self.viewing_distance(row, column, *direction))
+            .product()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ForestIndex;
+    use crate::{
+        calculate_scenic_score,
+        generator::{generate, Distribution},
+        is_visible, viewing_distance, Direction,
+    };
+
+    /// Checks the index against the scanning functions on every tree of
+    /// generated forests of all distributions, sizes and a few seeds.
+    #[test]
+    fn index_matches_scanning_functions() {
+        for distribution in [
+            Distribution::Uniform,
+            Distribution::SmoothTerrain,
+            Distribution::MonotoneRidges,
+        ] {
+            for (width, height) in [(1, 1), (1, 7), (9, 1), (2, 2), (17, 11), (40, 40)] {
+                for seed in 0..4 {
+                    let map = generate(width, height, distribution, seed);
+                    let index = ForestIndex::new(&map);
+                    for row in 0..height {
+                        for column in 0..width {
+                            assert_eq!(
+                                index.is_visible(row, column),
+                                is_visible(&map, row, column),
+                                "{distribution:?} {width}x{height}, seed {seed}, ({row}, {column})"
+                            );
+                            assert_eq!(
+                                index.scenic_score(row, column),
+                                calculate_scenic_score(&map, row, column),
+                                "{distribution:?} {width}x{height}, seed {seed}, ({row}, {column})"
+                            );
+                            for direction in Direction::ALL {
+                                assert_eq!(
+                                    index.viewing_distance(row, column, direction),
+                                    viewing_distance(&map, row, column, direction),
+                                    "{distribution:?} {width}x{height}, seed {seed}, ({row}, {column}) {direction}"
+                                );
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,163 @@
+use std::{
+    error::Error,
+    fmt::Display,
+    io::{self, Write},
+    str::FromStr,
+};
+
+/// A small, fast pseudo random number generator (SplitMix64). Good enough for
+/// test data and reproducible for a given seed.
+pub(crate) struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e3779b97f4a7c15);
+        mix(self.state)
+    }
+
+    /// Returns a number in `0..bound`.
+    pub fn below(&mut self, bound: u64) -> u64 {
+        self.next_u64() % bound
+    }
+}
+
+fn mix(mut value: u64) -> u64 {
+    value = (value ^ (value >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    value = (value ^ (value >> 27)).wrapping_mul(0x94d049bb133111eb);
+    value ^ (value >> 31)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Distribution {
+    /// Every height from 0 to 9 is equally likely.
+    Uniform,
+    /// Rolling hills made of several octaves of value noise.
+    SmoothTerrain,
+    /// Heights rise towards the middle of the map from all four edges, so
+    /// almost every line of sight runs downhill to the edge. This is the worst
+    /// case for the scanning functions, which only stop at a blocking tree.
+    MonotoneRidges,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct UnknownDistribution(String);
+
+impl Display for UnknownDistribution {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_fmt(format_args!(
+            "unknown distribution \"{}\" (expected \"uniform\", \"terrain\" or \"ridges\")",
+            self.0
+        ))
+    }
+}
+
+impl Error for UnknownDistribution {}
+
+impl FromStr for Distribution {
+    type Err = UnknownDistribution;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "uniform" => Ok(Distribution::Uniform),
+            "terrain" => Ok(Distribution::SmoothTerrain),
+            "ridges" => Ok(Distribution::MonotoneRidges),
+            _ => Err(UnknownDistribution(s.to_string())),
+        }
+    }
+}
+
+/// Value noise: random values on a lattice with the given spacing, bilinearly
+/// interpolated in between. Returns values in `0.0..1.0`.
+fn value_noise(seed: u64, spacing: usize, row: usize, column: usize) -> f64 {
+    let lattice_value = |lattice_row: usize, lattice_column: usize| {
+        let hash = mix(seed ^ mix((lattice_row as u64) << 32 | lattice_column as u64));
+        (hash >> 11) as f64 / (1u64 << 53) as f64
+    };
+    let (lattice_row, lattice_column) = (row / spacing, column / spacing);
+    let smooth = |t: f64| t * t * (3.0 - 2.0 * t);
+    let t_row = smooth((row % spacing) as f64 / spacing as f64);
+    let t_column = smooth((column % spacing) as f64 / spacing as f64);
+    let top = lattice_value(lattice_row, lattice_column) * (1.0 - t_column)
+        + lattice_value(lattice_row, lattice_column + 1) * t_column;
+    let bottom = lattice_value(lattice_row + 1, lattice_column) * (1.0 - t_column)
+        + lattice_value(lattice_row + 1, lattice_column + 1) * t_column;
+    top * (1.0 - t_row) + bottom * t_row
+}
+
+/// Generates a forest of the given size. The same seed always produces the
+/// same forest.
+#[must_use]
+pub(crate) fn generate(
+    width: usize,
+    height: usize,
+    distribution: Distribution,
+    seed: u64,
+) -> Vec<Vec<u8>> {
+    match distribution {
+        Distribution::Uniform => {
+            let mut rng = Rng::new(seed);
+            (0..height)
+                .map(|_| (0..width).map(|_| rng.below(10) as u8).collect())
+                .collect()
+        }
+        Distribution::SmoothTerrain => {
+            const OCTAVES: [(usize, f64); 4] = [(32, 0.5), (16, 0.25), (8, 0.15), (4, 0.1)];
+            (0..height)
+                .map(|row| {
+                    (0..width)
+                        .map(|column| {
+                            let value: f64 = OCTAVES
+                                .iter()
+                                .enumerate()
+                                .map(|(octave, (spacing, weight))| {
+                                    weight
+                                        * value_noise(
+                                            mix(seed.wrapping_add(octave as u64)),
+                                            *spacing,
+                                            row,
+                                            column,
+                                        )
+                                })
+                                .sum();
+                            (value * 10.0).clamp(0.0, 9.0) as u8
+                        })
+                        .collect()
+                })
+                .collect()
+        }
+        Distribution::MonotoneRidges => {
+            // the seed shifts the crest so that different seeds still differ
+            let mut rng = Rng::new(seed);
+            let crest_row = height / 2 + rng.below(height as u64 / 4 + 1) as usize;
+            let crest_column = width / 2 + rng.below(width as u64 / 4 + 1) as usize;
+            let max_distance = usize::max(crest_row, crest_column).max(1);
+            (0..height)
+                .map(|row| {
+                    (0..width)
+                        .map(|column| {
+                            let distance =
+                                usize::max(row.abs_diff(crest_row), column.abs_diff(crest_column));
+                            (9 - (9 * distance.min(max_distance) / max_distance)) as u8
+                        })
+                        .collect()
+                })
+                .collect()
+        }
+    }
+}
+
+/// Writes the forest in the same format as the puzzle input.
+pub(crate) fn write_forest(writer: &mut impl Write, map: &[Vec<u8>]) -> io::Result<()> {
+    for row in map {
+        let line: Vec<_> = row.iter().map(|height| b'0' + height).collect();
+        writer.write_all(&line)?;
+        writer.write_all(b"\n")?;
+    }
+    Ok(())
+}
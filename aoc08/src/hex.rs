@@ -0,0 +1,245 @@
+use std::{error::Error, fmt::Display, str::FromStr};
+
+/// The six directions on a hex grid in axial coordinates `(q, r)`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum HexDirection {
+    East,
+    NorthEast,
+    NorthWest,
+    West,
+    SouthWest,
+    SouthEast,
+}
+
+impl HexDirection {
+    pub const ALL: [HexDirection; 6] = [
+        HexDirection::East,
+        HexDirection::NorthEast,
+        HexDirection::NorthWest,
+        HexDirection::West,
+        HexDirection::SouthWest,
+        HexDirection::SouthEast,
+    ];
+
+    /// Offset `(dq, dr)` to the neighboring cell.
+    pub fn offset(&self) -> (isize, isize) {
+        match self {
+            HexDirection::East => (1, 0),
+            HexDirection::NorthEast => (1, -1),
+            HexDirection::NorthWest => (0, -1),
+            HexDirection::West => (-1, 0),
+            HexDirection::SouthWest => (-1, 1),
+            HexDirection::SouthEast => (0, 1),
+        }
+    }
+}
+
+/// A forest on a hex grid, stored in axial coordinates: `cells[r][q]` is the
+/// height of the tree at `(q, r)`, or `None` if that cell is not part of the
+/// forest.
+///
+/// In the text format every line is one row `r`. Spaces are ignored and may be
+/// used to indent rows so that the hexagons line up; every other character is
+/// one cell, either a digit for a tree or `.` for a cell outside of the forest.
+/// A hexagon of radius 1 is written as
+///
+/// ```text
+///  . 3 0
+///   2 5 5
+///    6 5 .
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct HexForest {
+    cells: Vec<Vec<Option<u8>>>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct HexParseError {
+    pub(crate) line: usize,
+    pub(crate) character: char,
+}
+
+impl Display for HexParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_fmt(format_args!(
+            "line {}: unexpected character '{}' in hex map",
+            self.line, self.character
+        ))
+    }
+}
+
+impl Error for HexParseError {}
+
+impl FromStr for HexForest {
+    type Err = HexParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut cells = Vec::new();
+        for (line_index, line) in s.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let row = line
+                .chars()
+                .filter(|c| *c != ' ')
+                .map(|c| match c {
+                    '.' => Ok(None),
+                    '0'..='9' => Ok(Some(c as u8 - b'0')),
+                    _ => Err(HexParseError {
+                        line: line_index + 1,
+                        character: c,
+                    }),
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            cells.push(row);
+        }
+        Ok(Self { cells })
+    }
+}
+
+impl HexForest {
+    fn height(&self, q: isize, r: isize) -> Option<u8> {
+        if q < 0 || r < 0 {
+            return None;
+        }
+        *self.cells.get(r as usize)?.get(q as usize)?
+    }
+
+    /// Iterates over the axial coordinates `(q, r)` of all trees.
+    pub fn trees(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.cells.iter().enumerate().flat_map(|(r, row)| {
+            row.iter()
+                .enumerate()
+                .filter(|(_, height)| height.is_some())
+                .map(move |(q, _)| (q, r))
+        })
+    }
+
+    /// Heights of all trees from `(q, r)` in the given direction up to the
+    /// edge of the forest, not including the starting tree itself.
+    fn heights_in_direction(
+        &self,
+        q: usize,
+        r: usize,
+        direction: HexDirection,
+    ) -> impl Iterator<Item = u8> + '_ {
+        let (dq, dr) = direction.offset();
+        (1..)
+            .map(move |steps| self.height(q as isize + dq * steps, r as isize + dr * steps))
+            .take_while(Option::is_some)
+            .map(Option::unwrap)
+    }
+
+    pub fn is_visible(&self, q: usize, r: usize) -> bool {
+        let height = self.cells[r][q].unwrap();
+        HexDirection::ALL.iter().any(|direction| {
+            self.heights_in_direction(q, r, *direction)
+                .all(|current_height| current_height < height)
+        })
+    }
+
+    pub fn viewing_distance(&self, q: usize, r: usize, direction: HexDirection) -> usize {
+        let height = self.cells[r][q].unwrap();
+        let mut distance = 0;
+        for current_height in self.heights_in_direction(q, r, direction) {
+            distance += 1;
+            if current_height >= height {
+                break;
+            }
+        }
+        distance
+    }
+
+    /// Product of the viewing distances in all six directions. As on the square
+    /// grid, trees on the edge of the forest score 0.
+    pub fn scenic_score(&self, q: usize, r: usize) -> usize {
+        HexDirection::ALL
+            .iter()
+            .map(|direction| self.viewing_distance(q, r, *direction))
+            .product()
+    }
+
+    pub fn visible_count(&self) -> usize {
+        self.trees()
+            .filter(|(q, r)| self.is_visible(*q, *r))
+            .count()
+    }
+
+    pub fn max_scenic_score(&self) -> usize {
+        self.trees()
+            .map(|(q, r)| self.scenic_score(q, r))
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{HexDirection, HexForest, HexParseError};
+
+    /// The hexagon of radius 1 from the documentation of `HexForest`.
+    const HEXAGON: &str = " . 3 0\n  2 5 5\n   6 5 .\n";
+
+    #[test]
+    fn parse() {
+        let forest: HexForest = HEXAGON.parse().unwrap();
+        assert_eq!(
+            forest.cells,
+            [
+                vec![None, Some(3), Some(0)],
+                vec![Some(2), Some(5), Some(5)],
+                vec![Some(6), Some(5), None],
+            ]
+        );
+        assert_eq!(
+            forest.trees().collect::<Vec<_>>(),
+            [(1, 0), (2, 0), (0, 1), (1, 1), (2, 1), (0, 2), (1, 2)]
+        );
+        // blank lines are skipped but still counted
+        assert_eq!(
+            "1 2\n\n3 x 4\n".parse::<HexForest>(),
+            Err(HexParseError {
+                line: 3,
+                character: 'x',
+            })
+        );
+        assert_eq!(
+            "1\t2".parse::<HexForest>(),
+            Err(HexParseError {
+                line: 1,
+                character: '\t',
+            })
+        );
+    }
+
+    #[test]
+    fn dots_are_edges() {
+        let forest: HexForest = "1 . 9\n".parse().unwrap();
+        assert_eq!(forest.viewing_distance(0, 0, HexDirection::East), 0);
+        assert!(forest.is_visible(2, 0));
+        let forest: HexForest = "1 5 9\n".parse().unwrap();
+        assert_eq!(forest.viewing_distance(0, 0, HexDirection::East), 1);
+        // surrounded on five sides, but the cell to the south east is not
+        // part of the forest
+        let forest: HexForest = ". 9 9\n9 1 9\n9 . 9\n".parse().unwrap();
+        assert!(forest.is_visible(1, 1));
+        assert_eq!(forest.viewing_distance(1, 1, HexDirection::SouthEast), 0);
+        assert_eq!(forest.viewing_distance(1, 1, HexDirection::West), 1);
+    }
+
+    #[test]
+    fn radius_one_hexagon() {
+        let forest: HexForest = HEXAGON.parse().unwrap();
+        // the center is visible from the north east, where the neighbour has
+        // height 0, and all other trees are on the edge
+        assert_eq!(forest.visible_count(), 7);
+        for direction in HexDirection::ALL {
+            assert_eq!(forest.viewing_distance(1, 1, direction), 1);
+        }
+        assert_eq!(forest.scenic_score(1, 1), 1);
+        assert_eq!(forest.scenic_score(0, 1), 0);
+        assert_eq!(forest.max_scenic_score(), 1);
+        let forest: HexForest = " . 6 6\n  6 5 6\n   6 6 .\n".parse().unwrap();
+        assert_eq!(forest.visible_count(), 6);
+    }
+}
@@ -0,0 +1,94 @@
+use std::num::NonZeroUsize;
+
+use crate::{
+    line_of_sight::{traverse, Point},
+    parallel,
+};
+
+/// Fraction of the horizon that can be seen from the top of the tree at
+/// `(row, column)`, sampled with `resolution` rays at evenly spaced azimuths.
+///
+/// Every cell is a solid column of its tree's height. A ray runs horizontally
+/// at the height of the viewing tree from its center to the edge of the map and
+/// is blocked by any column that is at least as tall, just like the views along
+/// rows and columns in the puzzle. Unlike `calculate_scenic_score`, which only
+/// looks in four directions, this also catches gaps between trees on
+/// diagonals and at arbitrary angles.
+pub(crate) fn visible_horizon_fraction(
+    map: &[Vec<u8>],
+    row: usize,
+    column: usize,
+    resolution: NonZeroUsize,
+) -> f64 {
+    let resolution = resolution.get();
+    let map_height = map.len();
+    let map_width = map[0].len();
+    let height = map[row][column];
+    let from = Point::tree_center(row, column);
+    // long enough to leave the map in every direction
+    let ray_length = (map_height + map_width) as f64;
+    let visible_rays = (0..resolution)
+        .filter(|ray| {
+            let azimuth = std::f64::consts::TAU * *ray as f64 / resolution as f64;
+            let to = Point {
+                row: from.row - azimuth.cos() * ray_length,
+                column: from.column + azimuth.sin() * ray_length,
+            };
+            traverse(map_height, map_width, from, to)
+                .iter()
+                .filter(|segment| (segment.row, segment.column) != (row, column))
+                .all(|segment| map[segment.row][segment.column] < height)
+        })
+        .count();
+    visible_rays as f64 / resolution as f64
+}
+
+#[must_use]
+pub(crate) fn visible_horizon_fractions(
+    map: &[Vec<u8>],
+    resolution: NonZeroUsize,
+    num_threads: usize,
+) -> Vec<Vec<f64>> {
+    parallel::analyze_cells(map, num_threads, |map, row, column| {
+        visible_horizon_fraction(map, row, column, resolution)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::num::NonZeroUsize;
+
+    use super::{visible_horizon_fraction, visible_horizon_fractions};
+    use crate::{parse_map, tests::example_map};
+
+    fn rays(resolution: usize) -> NonZeroUsize {
+        NonZeroUsize::new(resolution).unwrap()
+    }
+
+    #[test]
+    fn isolated_tall_tree() {
+        let map = parse_map("000\n090\n000\n");
+        assert_eq!(visible_horizon_fraction(&map, 1, 1, rays(360)), 1.0);
+    }
+
+    #[test]
+    fn boxed_in_tree() {
+        let map = parse_map("999\n919\n999\n");
+        assert_eq!(visible_horizon_fraction(&map, 1, 1, rays(360)), 0.0);
+        // as tall as its neighbours is not enough either
+        let map = parse_map("555\n555\n555\n");
+        assert_eq!(visible_horizon_fraction(&map, 1, 1, rays(360)), 0.0);
+    }
+
+    #[test]
+    fn example_forest() {
+        // from the tree of height 5 at (1, 1), the rays up, up-right,
+        // down-right, left and up-left are clear, while the ones to the right,
+        // down and down-left are blocked by trees of height 5, 5 and 6
+        let map = example_map();
+        assert_eq!(visible_horizon_fraction(&map, 1, 1, rays(8)), 5.0 / 8.0);
+        let fractions = visible_horizon_fractions(&map, rays(8), 2);
+        assert_eq!(fractions[1][1], 5.0 / 8.0);
+        assert_eq!(fractions.len(), 5);
+    }
+}
@@ -0,0 +1,237 @@
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::Path,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct Color {
+    pub(crate) red: u8,
+    pub(crate) green: u8,
+    pub(crate) blue: u8,
+}
+
+impl Color {
+    pub const BEST_TREE: Color = Color {
+        red: 255,
+        green: 0,
+        blue: 0,
+    };
+}
+
+/// Writes a binary greyscale image (PGM, magic number `P5`) to a file.
+pub(crate) fn write_pgm(
+    path: impl AsRef<Path>,
+    pixels: &[Vec<u8>],
+    scale: usize,
+) -> io::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    encode_pgm(&mut writer, pixels, scale)?;
+    writer.flush()
+}
+
+/// Writes a binary greyscale image (PGM, magic number `P5`). Every cell of
+/// `pixels` becomes a `scale`×`scale` block of pixels.
+fn encode_pgm(writer: &mut impl Write, pixels: &[Vec<u8>], scale: usize) -> io::Result<()> {
+    let width = pixels.first().map_or(0, Vec::len) * scale;
+    let height = pixels.len() * scale;
+    writer.write_fmt(format_args!("P5\n{width} {height}\n255\n"))?;
+    for row in pixels {
+        let scaled_row: Vec<_> = row
+            .iter()
+            .flat_map(|value| std::iter::repeat_n(*value, scale))
+            .collect();
+        for _ in 0..scale {
+            writer.write_all(&scaled_row)?;
+        }
+    }
+    Ok(())
+}
+
+/// Writes a binary color image (PPM, magic number `P6`) to a file.
+pub(crate) fn write_ppm(
+    path: impl AsRef<Path>,
+    pixels: &[Vec<Color>],
+    scale: usize,
+) -> io::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    encode_ppm(&mut writer, pixels, scale)?;
+    writer.flush()
+}
+
+/// Writes a binary color image (PPM, magic number `P6`). Every cell of
+/// `pixels` becomes a `scale`×`scale` block of pixels.
+fn encode_ppm(writer: &mut impl Write, pixels: &[Vec<Color>], scale: usize) -> io::Result<()> {
+    let width = pixels.first().map_or(0, Vec::len) * scale;
+    let height = pixels.len() * scale;
+    writer.write_fmt(format_args!("P6\n{width} {height}\n255\n"))?;
+    for row in pixels {
+        let scaled_row: Vec<_> = row
+            .iter()
+            .flat_map(|color| std::iter::repeat_n(*color, scale))
+            .flat_map(|color| [color.red, color.green, color.blue])
+            .collect();
+        for _ in 0..scale {
+            writer.write_all(&scaled_row)?;
+        }
+    }
+    Ok(())
+}
+
+/// Tree heights 0 to 9 are mapped to the full range from black to white.
+pub(crate) fn export_height_map(
+    map: &[Vec<u8>],
+    path: impl AsRef<Path>,
+    scale: usize,
+) -> io::Result<()> {
+    let pixels: Vec<Vec<_>> = map
+        .iter()
+        .map(|row| {
+            row.iter()
+                .map(|height| (*height as u32 * 255 / 9) as u8)
+                .collect()
+        })
+        .collect();
+    write_pgm(path, &pixels, scale)
+}
+
+/// Visible trees are white, hidden trees are black.
+pub(crate) fn export_visibility(
+    visibility: &[Vec<bool>],
+    path: impl AsRef<Path>,
+    scale: usize,
+) -> io::Result<()> {
+    let pixels: Vec<Vec<_>> = visibility
+        .iter()
+        .map(|row| {
+            row.iter()
+                .map(|visible| if *visible { 255 } else { 0 })
+                .collect()
+        })
+        .collect();
+    write_pgm(path, &pixels, scale)
+}
+
+fn heat_color(intensity: f64) -> Color {
+    const STOPS: [(f64, Color); 4] = [
+        (
+            0.0,
+            Color {
+                red: 0,
+                green: 0,
+                blue: 0,
+            },
+        ),
+        (
+            0.35,
+            Color {
+                red: 30,
+                green: 30,
+                blue: 160,
+            },
+        ),
+        (
+            0.75,
+            Color {
+                red: 255,
+                green: 200,
+                blue: 0,
+            },
+        ),
+        (
+            1.0,
+            Color {
+                red: 255,
+                green: 255,
+                blue: 255,
+            },
+        ),
+    ];
+    let intensity = intensity.clamp(0.0, 1.0);
+    let (start, end) = STOPS
+        .windows(2)
+        .map(|window| (window[0], window[1]))
+        .find(|(_, (end, _))| intensity <= *end)
+        .unwrap();
+    let t = (intensity - start.0) / (end.0 - start.0);
+    let mix = |from: u8, to: u8| (from as f64 + (to as f64 - from as f64) * t).round() as u8;
+    Color {
+        red: mix(start.1.red, end.1.red),
+        green: mix(start.1.green, end.1.green),
+        blue: mix(start.1.blue, end.1.blue),
+    }
+}
+
+/// Scenic scores span several orders of magnitude, so they are scaled
+/// logarithmically before being mapped onto a heat gradient. The tree with the
+/// highest score (the first one in reading order on ties) is drawn in red.
+pub(crate) fn export_scenic_scores(
+    scores: &[Vec<usize>],
+    path: impl AsRef<Path>,
+    scale: usize,
+) -> io::Result<()> {
+    let mut best: Option<(usize, usize, usize)> = None;
+    for (row, row_scores) in scores.iter().enumerate() {
+        for (column, score) in row_scores.iter().enumerate() {
+            if best.is_none_or(|(_, _, best_score)| *score > best_score) {
+                best = Some((row, column, *score));
+            }
+        }
+    }
+    let max_log = best.map_or(0.0, |(_, _, score)| (score as f64).ln_1p());
+    let mut pixels: Vec<Vec<_>> = scores
+        .iter()
+        .map(|row_scores| {
+            row_scores
+                .iter()
+                .map(|score| {
+                    if max_log == 0.0 {
+                        heat_color(0.0)
+                    } else {
+                        heat_color((*score as f64).ln_1p() / max_log)
+                    }
+                })
+                .collect()
+        })
+        .collect();
+    if let Some((row, column, _)) = best {
+        pixels[row][column] = Color::BEST_TREE;
+    }
+    write_ppm(path, &pixels, scale)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{encode_pgm, encode_ppm, Color};
+
+    #[test]
+    fn pgm_header_and_scaling() {
+        let mut output = Vec::new();
+        encode_pgm(&mut output, &[vec![0, 255], vec![7, 9]], 2).unwrap();
+        let header = b"P5\n4 4\n255\n";
+        assert_eq!(&output[..header.len()], header);
+        assert_eq!(
+            &output[header.len()..],
+            [0, 0, 255, 255, 0, 0, 255, 255, 7, 7, 9, 9, 7, 7, 9, 9]
+        );
+    }
+
+    #[test]
+    fn ppm_header_and_scaling() {
+        let mut output = Vec::new();
+        let white = Color {
+            red: 255,
+            green: 255,
+            blue: 255,
+        };
+        encode_ppm(&mut output, &[vec![Color::BEST_TREE, white]], 3).unwrap();
+        let header = b"P6\n6 3\n255\n";
+        assert_eq!(&output[..header.len()], header);
+        let row = [[255, 0, 0]; 3]
+            .into_iter()
+            .chain([[255; 3]; 3])
+            .flatten()
+            .collect::<Vec<u8>>();
+        assert_eq!(output[header.len()..], row.repeat(3));
+    }
+}
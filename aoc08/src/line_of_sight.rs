@@ -0,0 +1,334 @@
+use std::collections::BTreeSet;
+
+/// A point on the ground plane of the forest. The tree at `(row, column)`
+/// covers the unit square from `(row, column)` to `(row + 1, column + 1)`, so
+/// its center is at `(row + 0.5, column + 0.5)`. Points may lie outside of
+/// the grid.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct Point {
+    pub(crate) row: f64,
+    pub(crate) column: f64,
+}
+
+impl Point {
+    pub fn tree_center(row: usize, column: usize) -> Self {
+        Self {
+            row: row as f64 + 0.5,
+            column: column as f64 + 0.5,
+        }
+    }
+
+    fn cell(&self, map_height: usize, map_width: usize) -> Option<(usize, usize)> {
+        if self.row < 0.0
+            || self.column < 0.0
+            || self.row >= map_height as f64
+            || self.column >= map_width as f64
+        {
+            return None;
+        }
+        Some((self.row as usize, self.column as usize))
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct Observer {
+    pub(crate) position: Point,
+    pub(crate) eye_height: f64,
+}
+
+/// Part of a ray that lies within a single cell. `t_enter` and `t_exit` are
+/// the ray parameters (0 at the start, 1 at the end) where the ray enters and
+/// leaves the cell.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct RaySegment {
+    pub(crate) row: usize,
+    pub(crate) column: usize,
+    pub(crate) t_enter: f64,
+    pub(crate) t_exit: f64,
+}
+
+/// Clips the parameter range `[t_min, t_max]` of the ray `origin + t * delta`
+/// along one axis to the interval `[0, size]`.
+fn clip_axis(origin: f64, delta: f64, size: f64, t_min: &mut f64, t_max: &mut f64) -> bool {
+    if delta == 0.0 {
+        return origin >= 0.0 && origin <= size;
+    }
+    let (t0, t1) = {
+        let a = (0.0 - origin) / delta;
+        let b = (size - origin) / delta;
+        if a < b {
+            (a, b)
+        } else {
+            (b, a)
+        }
+    };
+    *t_min = t_min.max(t0);
+    *t_max = t_max.min(t1);
+    t_min < t_max
+}
+
+/// Returns every cell the straight line from `from` to `to` passes through,
+/// in order, restricted to the cells of a `map_height`×`map_width` grid.
+pub(crate) fn traverse(
+    map_height: usize,
+    map_width: usize,
+    from: Point,
+    to: Point,
+) -> Vec<RaySegment> {
+    let delta_row = to.row - from.row;
+    let delta_column = to.column - from.column;
+    let (mut t_start, mut t_end) = (0.0, 1.0);
+    if map_height == 0
+        || map_width == 0
+        || !clip_axis(
+            from.row,
+            delta_row,
+            map_height as f64,
+            &mut t_start,
+            &mut t_end,
+        )
+        || !clip_axis(
+            from.column,
+            delta_column,
+            map_width as f64,
+            &mut t_start,
+            &mut t_end,
+        )
+    {
+        return Vec::new();
+    }
+
+    // the cell we start in, nudged inwards if we start exactly on the far border
+    let start_row = from.row + t_start * delta_row;
+    let start_column = from.column + t_start * delta_column;
+    let initial_cell = |position: f64, delta: f64, size: usize| {
+        let cell = position.floor();
+        let cell = if delta < 0.0 && cell == position {
+            cell - 1.0
+        } else {
+            cell
+        };
+        cell.clamp(0.0, size as f64 - 1.0) as isize
+    };
+    let mut row = initial_cell(start_row, delta_row, map_height);
+    let mut column = initial_cell(start_column, delta_column, map_width);
+
+    // parameter of the next cell boundary along each axis, and the parameter
+    // distance between two boundaries
+    let boundary = |cell: isize, position: f64, delta: f64| {
+        if delta > 0.0 {
+            (
+                t_start + ((cell + 1) as f64 - position) / delta,
+                1.0 / delta,
+            )
+        } else if delta < 0.0 {
+            (t_start + (cell as f64 - position) / delta, -1.0 / delta)
+        } else {
+            (f64::INFINITY, f64::INFINITY)
+        }
+    };
+    let (mut t_next_row, t_step_row) = boundary(row, start_row, delta_row);
+    let (mut t_next_column, t_step_column) = boundary(column, start_column, delta_column);
+    let step_row = delta_row.signum() as isize;
+    let step_column = delta_column.signum() as isize;
+
+    let mut result = Vec::new();
+    let mut t_enter = t_start;
+    loop {
+        let t_exit = t_next_row.min(t_next_column).min(t_end);
+        if t_exit > t_enter || result.is_empty() {
+            result.push(RaySegment {
+                row: row as usize,
+                column: column as usize,
+                t_enter,
+                t_exit,
+            });
+        }
+        if t_exit >= t_end {
+            break;
+        }
+        if t_next_row < t_next_column {
+            row += step_row;
+            t_next_row += t_step_row;
+        } else {
+            column += step_column;
+            t_next_column += t_step_column;
+        }
+        if row < 0 || column < 0 || row >= map_height as isize || column >= map_width as isize {
+            break;
+        }
+        t_enter = t_exit;
+    }
+    result
+}
+
+/// Checks whether the observer can see the top of the tree at `(row, column)`.
+///
+/// Every cell is treated as a solid column of its tree's height. The sight line
+/// runs from the observer's eye to the top center of the target tree; any other
+/// tree whose column reaches the sight line while it crosses that tree's cell
+/// blocks the view. The cell the observer stands in never blocks.
+pub(crate) fn can_see(map: &[Vec<u8>], observer: &Observer, row: usize, column: usize) -> bool {
+    let map_height = map.len();
+    let map_width = map[0].len();
+    let observer_cell = observer.position.cell(map_height, map_width);
+    if observer_cell == Some((row, column)) {
+        return true;
+    }
+    let target_height = map[row][column] as f64;
+    let sight_height = |t: f64| observer.eye_height + (target_height - observer.eye_height) * t;
+    traverse(
+        map_height,
+        map_width,
+        observer.position,
+        Point::tree_center(row, column),
+    )
+    .iter()
+    .filter(|segment| {
+        let cell = Some((segment.row, segment.column));
+        cell != observer_cell && (segment.row, segment.column) != (row, column)
+    })
+    .all(|segment| {
+        let lowest_sight_height = sight_height(segment.t_enter).min(sight_height(segment.t_exit));
+        (map[segment.row][segment.column] as f64) < lowest_sight_height
+    })
+}
+
+/// Returns the coordinates of all trees the observer can see.
+pub(crate) fn visible_trees(map: &[Vec<u8>], observer: &Observer) -> BTreeSet<(usize, usize)> {
+    (0..map.len())
+        .flat_map(|row| (0..map[row].len()).map(move |column| (row, column)))
+        .filter(|(row, column)| can_see(map, observer, *row, *column))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{can_see, traverse, visible_trees, Observer, Point};
+
+    fn point(row: f64, column: f64) -> Point {
+        Point { row, column }
+    }
+
+    /// Traverses a 5×5 grid and returns the cells with their entry and exit
+    /// parameters.
+    fn cells(from: Point, to: Point) -> Vec<(usize, usize, f64, f64)> {
+        traverse(5, 5, from, to)
+            .into_iter()
+            .map(|segment| (segment.row, segment.column, segment.t_enter, segment.t_exit))
+            .collect()
+    }
+
+    fn assert_cells(actual: Vec<(usize, usize, f64, f64)>, expected: &[(usize, usize, f64, f64)]) {
+        assert_eq!(actual.len(), expected.len(), "{actual:?}");
+        for (actual, expected) in actual.iter().zip(expected) {
+            assert_eq!((actual.0, actual.1), (expected.0, expected.1), "{actual:?}");
+            assert!((actual.2 - expected.2).abs() < 1e-9, "{actual:?}");
+            assert!((actual.3 - expected.3).abs() < 1e-9, "{actual:?}");
+        }
+    }
+
+    #[test]
+    fn axis_aligned() {
+        assert_cells(
+            cells(Point::tree_center(0, 0), Point::tree_center(0, 3)),
+            &[
+                (0, 0, 0.0, 1.0 / 6.0),
+                (0, 1, 1.0 / 6.0, 0.5),
+                (0, 2, 0.5, 5.0 / 6.0),
+                (0, 3, 5.0 / 6.0, 1.0),
+            ],
+        );
+        assert_cells(
+            cells(Point::tree_center(2, 4), Point::tree_center(2, 4)),
+            &[(2, 4, 0.0, 1.0)],
+        );
+    }
+
+    #[test]
+    fn diagonal() {
+        assert_cells(
+            cells(Point::tree_center(0, 0), Point::tree_center(1, 2)),
+            &[
+                (0, 0, 0.0, 0.25),
+                (0, 1, 0.25, 0.5),
+                (1, 1, 0.5, 0.75),
+                (1, 2, 0.75, 1.0),
+            ],
+        );
+        // through the corners, the neighbouring cells are only touched
+        assert_cells(
+            cells(Point::tree_center(0, 0), Point::tree_center(2, 2)),
+            &[(0, 0, 0.0, 0.25), (1, 1, 0.25, 0.75), (2, 2, 0.75, 1.0)],
+        );
+    }
+
+    #[test]
+    fn start_on_a_cell_border() {
+        assert_cells(
+            cells(point(2.0, 0.5), point(0.5, 0.5)),
+            &[(1, 0, 0.0, 2.0 / 3.0), (0, 0, 2.0 / 3.0, 1.0)],
+        );
+        assert_cells(
+            cells(point(2.0, 0.5), point(3.5, 0.5)),
+            &[(2, 0, 0.0, 2.0 / 3.0), (3, 0, 2.0 / 3.0, 1.0)],
+        );
+    }
+
+    #[test]
+    fn start_outside_the_grid() {
+        assert_cells(
+            cells(point(-1.5, 0.5), point(1.5, 0.5)),
+            &[(0, 0, 0.5, 5.0 / 6.0), (1, 0, 5.0 / 6.0, 1.0)],
+        );
+        assert_cells(cells(point(-1.0, -1.0), point(-1.0, 6.0)), &[]);
+        assert_cells(cells(point(-1.0, 6.0), point(-3.0, 2.0)), &[]);
+    }
+
+    #[test]
+    fn observer_outside_the_grid() {
+        let map = vec![vec![1, 5, 3]];
+        let observer = Observer {
+            position: point(0.5, -1.0),
+            eye_height: 2.0,
+        };
+        assert_eq!(
+            visible_trees(&map, &observer)
+                .into_iter()
+                .collect::<Vec<_>>(),
+            [(0, 0), (0, 1)]
+        );
+    }
+
+    #[test]
+    fn the_observers_cell_never_blocks() {
+        let map = vec![vec![9, 1, 0]];
+        let observer = Observer {
+            position: Point::tree_center(0, 0),
+            eye_height: 0.5,
+        };
+        assert!(can_see(&map, &observer, 0, 0));
+        assert!(can_see(&map, &observer, 0, 1));
+        // the tree of height 1 is in the way of the low sight line
+        assert!(!can_see(&map, &observer, 0, 2));
+    }
+
+    #[test]
+    fn tall_trees_hide_shorter_ones() {
+        let observer = Observer {
+            position: Point::tree_center(0, 0),
+            eye_height: 1.0,
+        };
+        // the sight line to the tree of height 3 crosses the middle cell
+        // between heights 1.5 and 2.5
+        assert!(!can_see(&[vec![0, 5, 3]], &observer, 0, 2));
+        assert!(can_see(&[vec![0, 1, 3]], &observer, 0, 2));
+        let map = vec![vec![0, 2, 9, 4]];
+        assert_eq!(
+            visible_trees(&map, &observer)
+                .into_iter()
+                .collect::<Vec<_>>(),
+            [(0, 0), (0, 1), (0, 2)]
+        );
+    }
+}
@@ -1,4 +1,20 @@
-fn is_visible(map: &Vec<Vec<u8>>, row: usize, column: usize) -> bool {
+use std::{error::Error, fmt::Display, num::NonZeroUsize};
+
+mod candidates;
+mod explain;
+mod forest_analysis;
+mod forest_index;
+mod generator;
+mod hex;
+mod horizon;
+mod image;
+mod line_of_sight;
+mod parallel;
+mod removal;
+mod report;
+mod streaming;
+
+fn is_visible(map: &[Vec<u8>], row: usize, column: usize) -> bool {
     let height = map[row][column];
     if (0..column)
         .rev()
@@ -25,7 +41,7 @@ fn is_visible(map: &Vec<Vec<u8>>, row: usize, column: usize) -> bool {
         .all(|current_height| current_height < height)
 }
 
-fn calculate_scenic_score(map: &Vec<Vec<u8>>, row: usize, column: usize) -> usize {
+fn calculate_scenic_score(map: &[Vec<u8>], row: usize, column: usize) -> usize {
     let map_height = map.len();
     let map_width = map[0].len();
     let height = map[row][column];
@@ -57,16 +73,544 @@ fn calculate_scenic_score(map: &Vec<Vec<u8>>, row: usize, column: usize) -> usiz
     right_score * left_score * bottom_score * top_score
 }
 
-fn main() {
-    let input = std::fs::read_to_string("real_input.txt").unwrap();
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+enum Direction {
+    Up,
+    Left,
+    Down,
+    Right,
+}
+
+impl Direction {
+    pub const ALL: [Direction; 4] = [
+        Direction::Up,
+        Direction::Left,
+        Direction::Down,
+        Direction::Right,
+    ];
+
+    /// Returns the neighboring cell in this direction, or `None` if that would
+    /// leave a map of the given size.
+    pub fn step(
+        &self,
+        row: usize,
+        column: usize,
+        map_height: usize,
+        map_width: usize,
+    ) -> Option<(usize, usize)> {
+        match self {
+            Direction::Up => row.checked_sub(1).map(|row| (row, column)),
+            Direction::Left => column.checked_sub(1).map(|column| (row, column)),
+            Direction::Down => (row + 1 < map_height).then_some((row + 1, column)),
+            Direction::Right => (column + 1 < map_width).then_some((row, column + 1)),
+        }
+    }
+}
+
+impl Display for Direction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_fmt(format_args!(
+            "{}",
+            match self {
+                Direction::Up => "up",
+                Direction::Left => "left",
+                Direction::Down => "down",
+                Direction::Right => "right",
+            }
+        ))
+    }
+}
+
+/// Shape of the surface the forest grows on.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+enum Topology {
+    /// Lines of sight end at the edges of the map.
+    #[default]
+    Planar,
+    /// The map wraps around at its edges, so lines of sight leaving on one side
+    /// continue on the opposite side. They stop after one full lap, right
+    /// before reaching the starting tree again.
+    Toroidal,
+}
+
+impl Topology {
+    fn step(
+        &self,
+        direction: Direction,
+        row: usize,
+        column: usize,
+        map_height: usize,
+        map_width: usize,
+    ) -> Option<(usize, usize)> {
+        match self {
+            Topology::Planar => direction.step(row, column, map_height, map_width),
+            Topology::Toroidal => Some(match direction {
+                Direction::Up => ((row + map_height - 1) % map_height, column),
+                Direction::Left => (row, (column + map_width - 1) % map_width),
+                Direction::Down => ((row + 1) % map_height, column),
+                Direction::Right => (row, (column + 1) % map_width),
+            }),
+        }
+    }
+
+    /// Maximum number of trees on a line of sight in the given direction.
+    fn max_trees_in_direction(
+        &self,
+        direction: Direction,
+        map_height: usize,
+        map_width: usize,
+    ) -> usize {
+        match (self, direction) {
+            (Topology::Planar, _) => usize::MAX,
+            (Topology::Toroidal, Direction::Up | Direction::Down) => map_height - 1,
+            (Topology::Toroidal, Direction::Left | Direction::Right) => map_width - 1,
+        }
+    }
+}
+
+/// Iterates over the coordinates of all trees from `(row, column)` towards the
+/// edge of the map, not including the starting tree itself.
+fn trees_in_direction(
+    map: &[Vec<u8>],
+    row: usize,
+    column: usize,
+    direction: Direction,
+) -> impl Iterator<Item = (usize, usize)> {
+    trees_in_direction_on(map, row, column, direction, Topology::Planar)
+}
+
+/// Like `trees_in_direction`, but following the lines of sight of the given
+/// topology.
+fn trees_in_direction_on(
+    map: &[Vec<u8>],
+    row: usize,
+    column: usize,
+    direction: Direction,
+    topology: Topology,
+) -> impl Iterator<Item = (usize, usize)> {
+    let map_height = map.len();
+    let map_width = map[0].len();
+    std::iter::successors(Some((row, column)), move |(row, column)| {
+        topology.step(direction, *row, *column, map_height, map_width)
+    })
+    .skip(1)
+    .take(topology.max_trees_in_direction(direction, map_height, map_width))
+}
+
+/// Number of trees that can be seen from `(row, column)` when looking in the
+/// given direction: every tree up to and including the first one that is at
+/// least as tall, or up to the edge of the map.
+fn viewing_distance(map: &[Vec<u8>], row: usize, column: usize, direction: Direction) -> usize {
+    viewing_distance_on(map, row, column, direction, Topology::Planar)
+}
+
+fn viewing_distance_on(
+    map: &[Vec<u8>],
+    row: usize,
+    column: usize,
+    direction: Direction,
+    topology: Topology,
+) -> usize {
+    let height = map[row][column];
+    let mut distance = 0;
+    for (current_row, current_column) in
+        trees_in_direction_on(map, row, column, direction, topology)
+    {
+        distance += 1;
+        if map[current_row][current_column] >= height {
+            break;
+        }
+    }
+    distance
+}
+
+/// A tree is visible if, in at least one direction, every tree on its line of
+/// sight is shorter. On a torus this means being strictly taller than every
+/// other tree in its row or column.
+fn is_visible_on(map: &[Vec<u8>], row: usize, column: usize, topology: Topology) -> bool {
+    let height = map[row][column];
+    Direction::ALL.iter().any(|direction| {
+        trees_in_direction_on(map, row, column, *direction, topology)
+            .all(|(current_row, current_column)| map[current_row][current_column] < height)
+    })
+}
+
+/// Product of the viewing distances in all four directions, without any
+/// special treatment of edge trees.
+fn scenic_score_on(map: &[Vec<u8>], row: usize, column: usize, topology: Topology) -> usize {
+    Direction::ALL
+        .iter()
+        .map(|direction| viewing_distance_on(map, row, column, *direction, topology))
+        .product()
+}
+
+/// How trees at or close to the edge of the map are scored.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+enum EdgePolicy {
+    /// The puzzle's definition: trees on the edge score 0, all other trees
+    /// multiply their plain viewing distances. On the example forest the best
+    /// score is 8 (row 3, column 2) and the tree at row 0, column 2 scores 0.
+    #[default]
+    EdgeTreesScoreZero,
+    /// A view that reaches the edge without being blocked also sees the
+    /// horizon beyond it, which counts as one more tree. Edge trees therefore
+    /// look out onto the horizon instead of scoring 0. On the example forest
+    /// the best score is 45 (row 2, column 0), the puzzle's best tree at row 3,
+    /// column 2 scores 24 and the tree at row 0, column 2 scores 2.
+    OpenHorizon,
+    /// The full distance to the edge counts for unblocked views, and looking
+    /// straight off the map is neutral instead of zeroing the score. On the
+    /// example forest the best score is 16 (row 2, column 0), the puzzle's best
+    /// tree at row 3, column 2 keeps its score of 8 and the tree at row 0,
+    /// column 2 scores 2.
+    DistanceToEdge,
+}
+
+fn calculate_scenic_score_with_policy(
+    map: &[Vec<u8>],
+    row: usize,
+    column: usize,
+    policy: EdgePolicy,
+) -> usize {
+    let height = map[row][column];
+    let reaches_edge = |direction: Direction| {
+        trees_in_direction(map, row, column, direction)
+            .all(|(current_row, current_column)| map[current_row][current_column] < height)
+    };
+    match policy {
+        EdgePolicy::EdgeTreesScoreZero => calculate_scenic_score(map, row, column),
+        EdgePolicy::OpenHorizon => Direction::ALL
+            .iter()
+            .map(|direction| {
+                viewing_distance(map, row, column, *direction)
+                    + usize::from(reaches_edge(*direction))
+            })
+            .product(),
+        EdgePolicy::DistanceToEdge => Direction::ALL
+            .iter()
+            .map(|direction| viewing_distance(map, row, column, *direction).max(1))
+            .product(),
+    }
+}
+
+/// Returns the command line argument that follows `flag`, if any.
+fn argument_after(flag: &str) -> Option<String> {
+    std::env::args()
+        .skip_while(|argument| argument != flag)
+        .nth(1)
+}
+
+/// Parses a tree position given as `<row>,<column>` and checks that it lies on
+/// the map.
+fn parse_position(position: &str, map: &[Vec<u8>]) -> Result<(usize, usize), Box<dyn Error>> {
+    let (row, column) = position
+        .split_once(',')
+        .ok_or("position must be <row>,<column>")?;
+    let (row, column) = (
+        row.trim().parse::<usize>()?,
+        column.trim().parse::<usize>()?,
+    );
+    if row >= map.len() || column >= map[0].len() {
+        return Err("position is outside of the map".into());
+    }
+    Ok((row, column))
+}
+
+fn parse_map(input: &str) -> Vec<Vec<u8>> {
     let mut map = Vec::new();
-    for line in input.as_str().split('\n') {
+    for line in input.split('\n') {
         if line.trim().is_empty() {
             break;
         }
         let digits: Vec<_> = line.as_bytes().iter().map(|byte| *byte - b'0').collect();
         map.push(digits);
     }
+    map
+}
+
+/// Generates forests of every distribution and reports how long the scanning
+/// functions and the precomputed index take to analyze them.
+fn benchmark(width: usize, height: usize, seed: u64) {
+    for distribution in [
+        generator::Distribution::Uniform,
+        generator::Distribution::SmoothTerrain,
+        generator::Distribution::MonotoneRidges,
+    ] {
+        let map = generator::generate(width, height, distribution, seed);
+
+        let start = std::time::Instant::now();
+        let count = parallel::analyze_cells(&map, 1, is_visible)
+            .into_iter()
+            .flatten()
+            .filter(|visible| *visible)
+            .count();
+        let max_score = parallel::analyze_cells(&map, 1, calculate_scenic_score)
+            .into_iter()
+            .flatten()
+            .max()
+            .unwrap();
+        let scanning_time = start.elapsed();
+
+        let start = std::time::Instant::now();
+        let index = forest_index::ForestIndex::new(&map);
+        let index_build_time = start.elapsed();
+        let start = std::time::Instant::now();
+        let index_count =
+            parallel::analyze_cells(&map, 1, |_, row, column| index.is_visible(row, column))
+                .into_iter()
+                .flatten()
+                .filter(|visible| *visible)
+                .count();
+        let index_max_score =
+            parallel::analyze_cells(&map, 1, |_, row, column| index.scenic_score(row, column))
+                .into_iter()
+                .flatten()
+                .max()
+                .unwrap();
+        let index_query_time = start.elapsed();
+
+        println!(
+            "{distribution:?}: scanning {scanning_time:?} ({count} visible, max score {max_score}), building the index {index_build_time:?}, index queries {index_query_time:?} ({index_count} visible, max score {index_max_score})"
+        );
+    }
+}
+
+/// Reports errors by their message instead of their `Debug` form.
+fn main() {
+    if let Err(error) = solve() {
+        eprintln!("{error}");
+        std::process::exit(1);
+    }
+}
+
+fn solve() -> Result<(), Box<dyn Error>> {
+    // `--size <width>x<height>` and `--seed <n>` configure generated forests
+    let (width, height) = match argument_after("--size") {
+        Some(size) => {
+            let (width, height) = size
+                .split_once('x')
+                .ok_or("size must be <width>x<height>")?;
+            let (width, height): (usize, usize) = (width.parse()?, height.parse()?);
+            if width == 0 || height == 0 {
+                return Err("the forest must be at least 1x1".into());
+            }
+            (width, height)
+        }
+        None => (99, 99),
+    };
+    let seed = argument_after("--seed").map_or(Ok(0), |seed| seed.parse())?;
+
+    // `--generate <distribution>` writes a synthetic forest to stdout
+    if let Some(distribution) = argument_after("--generate") {
+        let map = generator::generate(width, height, distribution.parse()?, seed);
+        generator::write_forest(&mut std::io::BufWriter::new(std::io::stdout().lock()), &map)?;
+        return Ok(());
+    }
+
+    // `--benchmark` compares the scanning functions and the index on
+    // generated forests
+    if std::env::args().any(|argument| argument == "--benchmark") {
+        benchmark(width, height, seed);
+        return Ok(());
+    }
+
+    // `--hex <file>` analyzes a forest on a hex grid instead
+    if let Some(filename) = argument_after("--hex") {
+        let forest: hex::HexForest = std::fs::read_to_string(filename)?.parse()?;
+        println!("{}", forest.visible_count());
+        println!("max score: {}", forest.max_scenic_score());
+        return Ok(());
+    }
+
+    // `--mmap <file>` counts the visible trees of a forest that may not fit
+    // into memory
+    if let Some(filename) = argument_after("--mmap") {
+        let forest = streaming::MappedForest::open(filename)?;
+        println!("{}", forest.count_visible());
+        return Ok(());
+    }
+
+    // `--input <file>` reads another forest, for example a generated one
+    let path = argument_after("--input").unwrap_or_else(|| "real_input.txt".to_string());
+    let input = std::fs::read_to_string(path)?;
+    let map = parse_map(&input);
+    let num_threads = parallel::default_num_threads();
+
+    // `--explain <row>,<column>` explains the scenic score of a single tree
+    if let Some(position) = argument_after("--explain") {
+        let (row, column) = parse_position(&position, &map)?;
+        println!("{}", explain::explain(&map, row, column));
+        return Ok(());
+    }
+
+    // `--observer <row>,<column>,<eye height>` counts the trees an observer
+    // at that point of the ground plane can see, which may be outside the map
+    if let Some(observer) = argument_after("--observer") {
+        let values = observer
+            .split(',')
+            .map(|value| value.trim().parse::<f64>())
+            .collect::<Result<Vec<_>, _>>()?;
+        let [row, column, eye_height] = values[..] else {
+            return Err("observer must be <row>,<column>,<eye height>".into());
+        };
+        let observer = line_of_sight::Observer {
+            position: line_of_sight::Point { row, column },
+            eye_height,
+        };
+        println!(
+            "trees visible from ({row}, {column}) at eye height {eye_height}: {}",
+            line_of_sight::visible_trees(&map, &observer).len()
+        );
+        return Ok(());
+    }
+
+    // `--candidates <k>` ranks the best tree house positions, optionally
+    // restricted by `--min-edge-distance <n>` and `--min-height <height>`
+    if let Some(k) = argument_after("--candidates") {
+        let constraints = candidates::Constraints {
+            min_edge_distance: argument_after("--min-edge-distance")
+                .map_or(Ok(0), |distance| distance.parse())?,
+            min_height: argument_after("--min-height").map_or(Ok(0), |height| height.parse())?,
+        };
+        println!("best tree house candidates:");
+        for (rank, candidate) in candidates::top_candidates(&map, k.parse()?, &constraints)
+            .iter()
+            .enumerate()
+        {
+            println!("{:>3}. {candidate}", rank + 1);
+        }
+        return Ok(());
+    }
+
+    // `--cut <row>,<column>` shows what changes when that tree is cut down
+    if let Some(position) = argument_after("--cut") {
+        let (row, column) = parse_position(&position, &map)?;
+        let mut analysis = forest_analysis::ForestAnalysis::new(map);
+        println!(
+            "before cutting: {} visible trees, max score {}",
+            analysis.visible_count(),
+            analysis.max_scenic_score()
+        );
+        let height = analysis.map()[row][column];
+        analysis.set_height(row, column, 0);
+        println!(
+            "after cutting the tree of height {height} at row {row}, column {column}: {} visible trees, max score {}",
+            analysis.visible_count(),
+            analysis.max_scenic_score()
+        );
+        println!(
+            "the stump is {}visible and has a scenic score of {}",
+            if analysis.is_visible(row, column) {
+                ""
+            } else {
+                "not "
+            },
+            analysis.scenic_score(row, column)
+        );
+        return Ok(());
+    }
+
+    // `--edge-policies` compares the best scenic score under every edge policy
+    if std::env::args().any(|argument| argument == "--edge-policies") {
+        for policy in [
+            EdgePolicy::EdgeTreesScoreZero,
+            EdgePolicy::OpenHorizon,
+            EdgePolicy::DistanceToEdge,
+        ] {
+            let max_score = parallel::analyze_cells(&map, num_threads, |map, row, column| {
+                calculate_scenic_score_with_policy(map, row, column, policy)
+            })
+            .into_iter()
+            .flatten()
+            .max()
+            .unwrap();
+            println!("max score ({policy:?}): {max_score}");
+        }
+        return Ok(());
+    }
+
+    // `--torus` analyzes the forest as if its edges wrapped around
+    if std::env::args().any(|argument| argument == "--torus") {
+        let topology = Topology::Toroidal;
+        let toroidal_count = parallel::analyze_cells(&map, num_threads, |map, row, column| {
+            is_visible_on(map, row, column, topology)
+        })
+        .into_iter()
+        .flatten()
+        .filter(|visible| *visible)
+        .count();
+        let toroidal_max_score = parallel::analyze_cells(&map, num_threads, |map, row, column| {
+            scenic_score_on(map, row, column, topology)
+        })
+        .into_iter()
+        .flatten()
+        .max()
+        .unwrap();
+        println!("on a torus: {toroidal_count} visible trees, max score {toroidal_max_score}");
+        return Ok(());
+    }
+
+    // `--removal <row>,<column>` finds the fewest and the lowest trees to cut
+    // so that the tree at that position becomes visible
+    if let Some(position) = argument_after("--removal") {
+        let (row, column) = parse_position(&position, &map)?;
+        for cost in [
+            removal::RemovalCost::TreeCount,
+            removal::RemovalCost::TotalHeight,
+        ] {
+            let removal = removal::minimal_removal(&map, row, column, cost);
+            println!("minimizing {cost:?}: {removal}");
+        }
+        return Ok(());
+    }
+
+    // `--horizon <rays>` finds the tree that sees the largest part of the
+    // horizon when looking in that many evenly spaced directions
+    if let Some(resolution) = argument_after("--horizon") {
+        let resolution: NonZeroUsize = resolution.parse()?;
+        let horizon_fractions = horizon::visible_horizon_fractions(&map, resolution, num_threads);
+        let best = horizon_fractions
+            .iter()
+            .enumerate()
+            .flat_map(|(row, fractions)| {
+                fractions
+                    .iter()
+                    .enumerate()
+                    .map(move |(column, fraction)| ((row, column), *fraction))
+            })
+            .filter(|((row, column), _)| {
+                *row > 0 && *column > 0 && *row < map.len() - 1 && *column < map[0].len() - 1
+            })
+            .fold(None, |best: Option<((usize, usize), f64)>, current| {
+                if best.is_none_or(|best| current.1 > best.1) {
+                    Some(current)
+                } else {
+                    best
+                }
+            });
+        match best {
+            Some(((row, column), fraction)) => println!(
+                "widest view of the horizon ({resolution} rays, edge trees excluded): row {row}, column {column}, {:.1}%",
+                fraction * 100.0
+            ),
+            None => println!("the forest has no trees away from the edge"),
+        }
+        return Ok(());
+    }
+
+    // `--format csv` or `--format json` switches to structured per-tree output
+    let output_format = argument_after("--format")
+        .map(|format| format.parse::<report::OutputFormat>())
+        .transpose()?;
+    if let Some(format) = output_format {
+        let index = forest_index::ForestIndex::new(&map);
+        let mut stdout = std::io::BufWriter::new(std::io::stdout().lock());
+        let summary = report::write_report(&mut stdout, &map, &index, format)?;
+        if format == report::OutputFormat::Csv {
+            eprintln!("{summary}");
+        }
+        return Ok(());
+    }
 
     for row in &map {
         for height in row {
@@ -76,12 +620,20 @@ fn main() {
     }
     println!();
 
+    let index = forest_index::ForestIndex::new(&map);
+    let visibility = parallel::analyze_cells(&map, num_threads, |_, row, column| {
+        index.is_visible(row, column)
+    });
+    let scenic_scores = parallel::analyze_cells(&map, num_threads, |_, row, column| {
+        index.scenic_score(row, column)
+    });
+
     let mut count = 0;
-    for (row, _) in map.iter().enumerate() {
-        for (column, _) in map[row].iter().enumerate() {
+    for row in &visibility {
+        for visible in row {
             print!(
                 "{}",
-                if is_visible(&map, row, column) {
+                if *visible {
                     count += 1;
                     'V'
                 } else {
@@ -93,18 +645,158 @@ fn main() {
     }
     println!("{}", count);
 
-    for row in 0..map.len() {
-        for column in 0..map[0].len() {
-            let score = calculate_scenic_score(&map, row, column);
+    for row in &scenic_scores {
+        for score in row {
             print!("{score}, ");
         }
         println!();
     }
 
-    let max_score = (0..map.len())
-        .flat_map(|row| (0..map[0].len()).map(move |column| (row, column)))
-        .map(|(row, column)| calculate_scenic_score(&map, row, column))
-        .max()
-        .unwrap();
+    let max_score = scenic_scores.iter().flatten().max().unwrap();
     println!("max score: {max_score}");
+
+    // `--images <directory>` exports the height map, the visibility mask and a
+    // heatmap of the scenic scores
+    if let Some(directory) = argument_after("--images") {
+        const IMAGE_SCALE: usize = 4;
+        let directory = std::path::Path::new(&directory);
+        std::fs::create_dir_all(directory)?;
+        image::export_height_map(&map, directory.join("heights.pgm"), IMAGE_SCALE)?;
+        image::export_visibility(&visibility, directory.join("visibility.pgm"), IMAGE_SCALE)?;
+        image::export_scenic_scores(
+            &scenic_scores,
+            directory.join("scenic_scores.ppm"),
+            IMAGE_SCALE,
+        )?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        calculate_scenic_score_with_policy, is_visible_on, scenic_score_on, trees_in_direction_on,
+        viewing_distance_on, Direction, EdgePolicy, Topology,
+    };
+
+    /// The example forest from the puzzle description.
+    pub(crate) fn example_map() -> Vec<Vec<u8>> {
+        super::parse_map("30373\n25512\n65332\n33549\n35390\n")
+    }
+
+    /// Returns the best score under the policy and the first tree in reading
+    /// order that has it.
+    fn best_tree(map: &[Vec<u8>], policy: EdgePolicy) -> (usize, (usize, usize)) {
+        let mut best = (0, (0, 0));
+        for row in 0..map.len() {
+            for column in 0..map[row].len() {
+                let score = calculate_scenic_score_with_policy(map, row, column, policy);
+                if score > best.0 {
+                    best = (score, (row, column));
+                }
+            }
+        }
+        best
+    }
+
+    #[test]
+    fn edge_trees_score_zero() {
+        let map = example_map();
+        let policy = EdgePolicy::EdgeTreesScoreZero;
+        assert_eq!(best_tree(&map, policy), (8, (3, 2)));
+        assert_eq!(calculate_scenic_score_with_policy(&map, 0, 2, policy), 0);
+        assert_eq!(calculate_scenic_score_with_policy(&map, 1, 2, policy), 4);
+    }
+
+    #[test]
+    fn open_horizon() {
+        let map = example_map();
+        let policy = EdgePolicy::OpenHorizon;
+        assert_eq!(best_tree(&map, policy), (45, (2, 0)));
+        assert_eq!(calculate_scenic_score_with_policy(&map, 3, 2, policy), 24);
+        assert_eq!(calculate_scenic_score_with_policy(&map, 0, 2, policy), 2);
+    }
+
+    #[test]
+    fn distance_to_edge() {
+        let map = example_map();
+        let policy = EdgePolicy::DistanceToEdge;
+        assert_eq!(best_tree(&map, policy), (16, (2, 0)));
+        assert_eq!(calculate_scenic_score_with_policy(&map, 3, 2, policy), 8);
+        assert_eq!(calculate_scenic_score_with_policy(&map, 0, 2, policy), 2);
+    }
+
+    #[test]
+    fn torus_stops_after_one_lap() {
+        let map = example_map();
+        let trees = |row, column, direction| {
+            trees_in_direction_on(&map, row, column, direction, Topology::Toroidal)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            trees(0, 0, Direction::Right),
+            [(0, 1), (0, 2), (0, 3), (0, 4)]
+        );
+        assert_eq!(
+            trees(0, 0, Direction::Left),
+            [(0, 4), (0, 3), (0, 2), (0, 1)]
+        );
+        assert_eq!(
+            trees(2, 3, Direction::Down),
+            [(3, 3), (4, 3), (0, 3), (1, 3)]
+        );
+        // a tree taller than the rest of its row sees all of it, but only once
+        let map = vec![vec![5, 1, 1, 1, 1]];
+        assert_eq!(
+            viewing_distance_on(&map, 0, 0, Direction::Right, Topology::Toroidal),
+            4
+        );
+        assert_eq!(
+            viewing_distance_on(&map, 0, 2, Direction::Right, Topology::Toroidal),
+            1
+        );
+    }
+
+    #[test]
+    fn torus_wraps_around() {
+        let map = vec![vec![3, 1, 5], vec![4, 0, 0], vec![0, 0, 0]];
+        // on the plane the corner tree is visible, on the torus the tree of
+        // height 5 blocks it from the left and the tree of height 4 from above
+        assert!(is_visible_on(&map, 0, 0, Topology::Planar));
+        assert!(!is_visible_on(&map, 0, 0, Topology::Toroidal));
+        assert!(is_visible_on(&map, 0, 2, Topology::Toroidal));
+        assert_eq!(scenic_score_on(&map, 0, 0, Topology::Planar), 0);
+        // up 2, left 1, down 1, right 2
+        assert_eq!(scenic_score_on(&map, 0, 0, Topology::Toroidal), 4);
+    }
+
+    #[test]
+    fn torus_of_a_single_row_or_column() {
+        let row = vec![vec![2, 7, 2, 1]];
+        let column: Vec<_> = row[0].iter().map(|height| vec![*height]).collect();
+        for (map, across) in [(&row, Direction::Up), (&column, Direction::Left)] {
+            // there are no other trees across the line, so every tree is
+            // visible and scores 0
+            let trees: Vec<_> = (0..4)
+                .map(|i| if map.len() == 1 { (0, i) } else { (i, 0) })
+                .collect();
+            for (row, column) in trees {
+                assert!(is_visible_on(map, row, column, Topology::Toroidal));
+                assert_eq!(
+                    trees_in_direction_on(map, row, column, across, Topology::Toroidal).count(),
+                    0
+                );
+                assert_eq!(scenic_score_on(map, row, column, Topology::Toroidal), 0);
+            }
+        }
+        let along = |map, row, column, direction| {
+            viewing_distance_on(map, row, column, direction, Topology::Toroidal)
+        };
+        assert_eq!(along(&row, 0, 1, Direction::Right), 3);
+        // the view wraps around to the first tree, which is as tall
+        assert_eq!(along(&row, 0, 2, Direction::Right), 2);
+        assert_eq!(along(&column, 2, 0, Direction::Down), 2);
+        assert_eq!(along(&column, 3, 0, Direction::Up), 1);
+    }
 }
@@ -0,0 +1,75 @@
+use std::{num::NonZeroUsize, thread};
+
+/// Number of worker threads to use when the caller has no preference.
+pub(crate) fn default_num_threads() -> usize {
+    thread::available_parallelism().map_or(1, NonZeroUsize::get)
+}
+
+/// Applies `analyze` to every cell of the map and returns the results as a
+/// grid. The rows are split into contiguous bands, one per thread, and the
+/// bands are stitched back together in order, so the result is identical to
+/// evaluating the cells one after another.
+#[must_use]
+pub(crate) fn analyze_cells<T, F>(map: &[Vec<u8>], num_threads: usize, analyze: F) -> Vec<Vec<T>>
+where
+    T: Send,
+    F: Fn(&[Vec<u8>], usize, usize) -> T + Sync,
+{
+    let analyze_rows = |rows: std::ops::Range<usize>| -> Vec<Vec<T>> {
+        rows.map(|row| {
+            (0..map[row].len())
+                .map(|column| analyze(map, row, column))
+                .collect()
+        })
+        .collect()
+    };
+    let num_threads = num_threads.clamp(1, map.len().max(1));
+    if num_threads == 1 {
+        return analyze_rows(0..map.len());
+    }
+    let rows_per_thread = map.len().div_ceil(num_threads);
+    thread::scope(|scope| {
+        let handles: Vec<_> = (0..map.len())
+            .step_by(rows_per_thread)
+            .map(|start| {
+                let end = usize::min(start + rows_per_thread, map.len());
+                let analyze_rows = &analyze_rows;
+                scope.spawn(move || analyze_rows(start..end))
+            })
+            .collect();
+        handles
+            .into_iter()
+            .flat_map(|handle| handle.join().unwrap())
+            .collect()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::analyze_cells;
+    use crate::{
+        calculate_scenic_score,
+        generator::{generate, Distribution},
+        is_visible,
+    };
+
+    #[test]
+    fn parallel_results_match_sequential_results() {
+        let map = generate(37, 23, Distribution::Uniform, 7);
+        let visibility = analyze_cells(&map, 1, is_visible);
+        let scenic_scores = analyze_cells(&map, 1, calculate_scenic_score);
+        // 64 threads is more than there are rows
+        for num_threads in [2, 3, 4, 8, 22, 23, 64] {
+            assert_eq!(analyze_cells(&map, num_threads, is_visible), visibility);
+            assert_eq!(
+                analyze_cells(&map, num_threads, calculate_scenic_score),
+                scenic_scores
+            );
+        }
+    }
+
+    #[test]
+    fn empty_map() {
+        assert!(analyze_cells(&[], 4, |_, _, _| 0).is_empty());
+    }
+}
@@ -0,0 +1,151 @@
+use std::fmt::Display;
+
+use crate::{trees_in_direction, Direction};
+
+/// What to minimize when choosing trees to cut.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum RemovalCost {
+    /// The number of trees that are cut.
+    TreeCount,
+    /// The sum of the heights of all trees that are cut.
+    TotalHeight,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Removal {
+    /// The edge the target tree becomes visible from.
+    pub(crate) direction: Direction,
+    /// Coordinates of the trees to cut, ordered from the target tree outwards.
+    pub(crate) trees: Vec<(usize, usize)>,
+    pub(crate) total_height: usize,
+}
+
+impl Removal {
+    pub fn cost(&self, cost: RemovalCost) -> usize {
+        match cost {
+            RemovalCost::TreeCount => self.trees.len(),
+            RemovalCost::TotalHeight => self.total_height,
+        }
+    }
+}
+
+impl Display for Removal {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let trees: Vec<_> = self
+            .trees
+            .iter()
+            .map(|(row, column)| format!("({row}, {column})"))
+            .collect();
+        f.write_fmt(format_args!(
+            "cut {} trees with a total height of {} to be visible from {}: {}",
+            self.trees.len(),
+            self.total_height,
+            self.direction,
+            trees.join(", ")
+        ))
+    }
+}
+
+/// Finds the cheapest way to make the tree at `(row, column)` visible from at
+/// least one edge. Looking from a single direction, exactly the trees that are
+/// at least as tall as the target have to go, so the optimum is the cheapest
+/// of the four directions. Ties are resolved in the order of `Direction::ALL`.
+/// A tree that is already visible needs no trees to be cut.
+#[must_use]
+pub(crate) fn minimal_removal(
+    map: &[Vec<u8>],
+    row: usize,
+    column: usize,
+    cost: RemovalCost,
+) -> Removal {
+    let height = map[row][column];
+    Direction::ALL
+        .iter()
+        .map(|direction| {
+            let trees: Vec<_> = trees_in_direction(map, row, column, *direction)
+                .filter(|(current_row, current_column)| {
+                    map[*current_row][*current_column] >= height
+                })
+                .collect();
+            let total_height = trees
+                .iter()
+                .map(|(current_row, current_column)| map[*current_row][*current_column] as usize)
+                .sum();
+            Removal {
+                direction: *direction,
+                trees,
+                total_height,
+            }
+        })
+        .min_by_key(|removal| removal.cost(cost))
+        .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{minimal_removal, Removal, RemovalCost};
+    use crate::{parse_map, tests::example_map, Direction};
+
+    #[test]
+    fn count_and_height_disagree() {
+        // the tree in the middle hides behind one tree of height 9 above it and
+        // behind two trees of height 1 to its left
+        let map = parse_map("00900\n00000\n11122\n00500\n00500\n");
+        assert_eq!(
+            minimal_removal(&map, 2, 2, RemovalCost::TreeCount),
+            Removal {
+                direction: Direction::Up,
+                trees: vec![(0, 2)],
+                total_height: 9,
+            }
+        );
+        assert_eq!(
+            minimal_removal(&map, 2, 2, RemovalCost::TotalHeight),
+            Removal {
+                direction: Direction::Left,
+                trees: vec![(2, 1), (2, 0)],
+                total_height: 2,
+            }
+        );
+    }
+
+    #[test]
+    fn example_forest() {
+        let map = example_map();
+        let removal = minimal_removal(&map, 2, 2, RemovalCost::TreeCount);
+        assert_eq!(
+            removal,
+            minimal_removal(&map, 2, 2, RemovalCost::TotalHeight)
+        );
+        assert_eq!(
+            removal.to_string(),
+            "cut 1 trees with a total height of 3 to be visible from right: (2, 3)"
+        );
+    }
+
+    #[test]
+    fn visible_trees_need_no_cuts() {
+        let map = example_map();
+        for cost in [RemovalCost::TreeCount, RemovalCost::TotalHeight] {
+            // the first direction without obstacles is chosen
+            assert_eq!(
+                minimal_removal(&map, 0, 0, cost),
+                Removal {
+                    direction: Direction::Up,
+                    trees: Vec::new(),
+                    total_height: 0,
+                }
+            );
+            assert_eq!(
+                minimal_removal(&map, 1, 2, cost),
+                Removal {
+                    direction: Direction::Up,
+                    trees: Vec::new(),
+                    total_height: 0,
+                }
+            );
+            assert_eq!(minimal_removal(&map, 3, 2, cost).direction, Direction::Left);
+            assert!(minimal_removal(&map, 3, 2, cost).trees.is_empty());
+        }
+    }
+}
@@ -0,0 +1,249 @@
+use std::{
+    error::Error,
+    fmt::Display,
+    io::{self, Write},
+    str::FromStr,
+};
+
+use crate::{forest_index::ForestIndex, Direction};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum OutputFormat {
+    Csv,
+    /// One JSON object per line.
+    Json,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct UnknownOutputFormat(String);
+
+impl Display for UnknownOutputFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_fmt(format_args!(
+            "unknown output format \"{}\" (expected \"csv\" or \"json\")",
+            self.0
+        ))
+    }
+}
+
+impl Error for UnknownOutputFormat {}
+
+impl FromStr for OutputFormat {
+    type Err = UnknownOutputFormat;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "csv" => Ok(OutputFormat::Csv),
+            "json" => Ok(OutputFormat::Json),
+            _ => Err(UnknownOutputFormat(s.to_string())),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct TreeRecord {
+    pub(crate) row: usize,
+    pub(crate) column: usize,
+    pub(crate) height: u8,
+    /// Visibility from the edges, in the order of `Direction::ALL`.
+    pub(crate) visible_from: [bool; 4],
+    /// Viewing distances, in the order of `Direction::ALL`.
+    pub(crate) viewing_distances: [usize; 4],
+}
+
+impl TreeRecord {
+    pub fn new(map: &[Vec<u8>], index: &ForestIndex, row: usize, column: usize) -> Self {
+        Self {
+            row,
+            column,
+            height: map[row][column],
+            visible_from: Direction::ALL
+                .map(|direction| index.is_visible_from(row, column, direction)),
+            viewing_distances: Direction::ALL
+                .map(|direction| index.viewing_distance(row, column, direction)),
+        }
+    }
+
+    pub fn is_visible(&self) -> bool {
+        self.visible_from.contains(&true)
+    }
+
+    pub fn scenic_score(&self) -> usize {
+        self.viewing_distances.iter().product()
+    }
+
+    fn csv_header() -> String {
+        let mut columns = vec![
+            "row".to_string(),
+            "column".to_string(),
+            "height".to_string(),
+            "visible".to_string(),
+        ];
+        columns.extend(
+            Direction::ALL
+                .iter()
+                .map(|direction| format!("visible_from_{direction}")),
+        );
+        columns.extend(
+            Direction::ALL
+                .iter()
+                .map(|direction| format!("viewing_distance_{direction}")),
+        );
+        columns.push("scenic_score".to_string());
+        columns.join(",")
+    }
+
+    fn to_csv(self) -> String {
+        let mut fields = vec![
+            self.row.to_string(),
+            self.column.to_string(),
+            self.height.to_string(),
+            self.is_visible().to_string(),
+        ];
+        fields.extend(self.visible_from.iter().map(bool::to_string));
+        fields.extend(self.viewing_distances.iter().map(usize::to_string));
+        fields.push(self.scenic_score().to_string());
+        fields.join(",")
+    }
+
+    fn to_json(self) -> String {
+        let per_direction = |values: Vec<String>| {
+            let entries: Vec<_> = Direction::ALL
+                .iter()
+                .zip(values)
+                .map(|(direction, value)| format!("\"{direction}\":{value}"))
+                .collect();
+            format!("{{{}}}", entries.join(","))
+        };
+        format!(
+            "{{\"row\":{},\"column\":{},\"height\":{},\"visible\":{},\"visible_from\":{},\"viewing_distances\":{},\"scenic_score\":{}}}",
+            self.row,
+            self.column,
+            self.height,
+            self.is_visible(),
+            per_direction(self.visible_from.iter().map(bool::to_string).collect()),
+            per_direction(self.viewing_distances.iter().map(usize::to_string).collect()),
+            self.scenic_score(),
+        )
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) struct Summary {
+    pub(crate) visible_count: usize,
+    pub(crate) max_scenic_score: usize,
+}
+
+impl Summary {
+    fn to_json(self) -> String {
+        format!(
+            "{{\"summary\":{{\"visible_count\":{},\"max_scenic_score\":{}}}}}",
+            self.visible_count, self.max_scenic_score
+        )
+    }
+}
+
+impl Display for Summary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.to_json())
+    }
+}
+
+/// Writes one record per tree in reading order and returns the summary.
+///
+/// In JSON mode the summary is written as the last line. CSV has no place for
+/// it, so the caller decides where to put it.
+pub(crate) fn write_report(
+    writer: &mut impl Write,
+    map: &[Vec<u8>],
+    index: &ForestIndex,
+    format: OutputFormat,
+) -> io::Result<Summary> {
+    if format == OutputFormat::Csv {
+        writeln!(writer, "{}", TreeRecord::csv_header())?;
+    }
+    let mut summary = Summary::default();
+    for row in 0..map.len() {
+        for column in 0..map[row].len() {
+            let record = TreeRecord::new(map, index, row, column);
+            if record.is_visible() {
+                summary.visible_count += 1;
+            }
+            summary.max_scenic_score = summary.max_scenic_score.max(record.scenic_score());
+            match format {
+                OutputFormat::Csv => writeln!(writer, "{}", record.to_csv())?,
+                OutputFormat::Json => writeln!(writer, "{}", record.to_json())?,
+            }
+        }
+    }
+    if format == OutputFormat::Json {
+        writeln!(writer, "{}", summary.to_json())?;
+    }
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{write_report, OutputFormat, Summary};
+    use crate::{forest_index::ForestIndex, tests::example_map};
+
+    fn report(format: OutputFormat) -> (Vec<String>, Summary) {
+        let map = example_map();
+        let mut output = Vec::new();
+        let summary = write_report(&mut output, &map, &ForestIndex::new(&map), format).unwrap();
+        let lines = String::from_utf8(output)
+            .unwrap()
+            .lines()
+            .map(str::to_string)
+            .collect();
+        (lines, summary)
+    }
+
+    const EXAMPLE_SUMMARY: Summary = Summary {
+        visible_count: 21,
+        max_scenic_score: 8,
+    };
+
+    #[test]
+    fn csv() {
+        let (lines, summary) = report(OutputFormat::Csv);
+        assert_eq!(summary, EXAMPLE_SUMMARY);
+        assert_eq!(lines.len(), 1 + 25);
+        assert_eq!(
+            lines[0],
+            "row,column,height,visible,\
+             visible_from_up,visible_from_left,visible_from_down,visible_from_right,\
+             viewing_distance_up,viewing_distance_left,viewing_distance_down,viewing_distance_right,\
+             scenic_score"
+        );
+        assert_eq!(lines[1], "0,0,3,true,true,true,false,false,0,0,2,2,0");
+        assert_eq!(lines[2], "0,1,0,true,true,false,false,false,0,1,1,1,0");
+        assert_eq!(lines[1 + 17], "3,2,5,true,false,true,true,false,2,2,1,2,8");
+    }
+
+    #[test]
+    fn json() {
+        let (lines, summary) = report(OutputFormat::Json);
+        assert_eq!(summary, EXAMPLE_SUMMARY);
+        assert_eq!(lines.len(), 25 + 1);
+        assert_eq!(
+            lines[0],
+            "{\"row\":0,\"column\":0,\"height\":3,\"visible\":true,\
+             \"visible_from\":{\"up\":true,\"left\":true,\"down\":false,\"right\":false},\
+             \"viewing_distances\":{\"up\":0,\"left\":0,\"down\":2,\"right\":2},\
+             \"scenic_score\":0}"
+        );
+        assert_eq!(
+            lines[17],
+            "{\"row\":3,\"column\":2,\"height\":5,\"visible\":true,\
+             \"visible_from\":{\"up\":false,\"left\":true,\"down\":true,\"right\":false},\
+             \"viewing_distances\":{\"up\":2,\"left\":2,\"down\":1,\"right\":2},\
+             \"scenic_score\":8}"
+        );
+        assert_eq!(
+            lines[25],
+            "{\"summary\":{\"visible_count\":21,\"max_scenic_score\":8}}"
+        );
+        assert_eq!(summary.to_string(), lines[25]);
+    }
+}
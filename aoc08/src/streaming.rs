@@ -0,0 +1,190 @@
+use std::{
+    fs::File,
+    io::{self, ErrorKind},
+    path::Path,
+};
+
+use memmap2::Mmap;
+
+/// A forest file that is memory-mapped and read in place. Row `row` starts at
+/// byte `row * stride`, where the stride is the width of the map plus the
+/// length of the line break (`\n` or `\r\n`).
+pub(crate) struct MappedForest {
+    mmap: Mmap,
+    width: usize,
+    height: usize,
+    stride: usize,
+}
+
+impl MappedForest {
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let file = File::open(path)?;
+        // SAFETY: the file must not be modified while it is mapped. We only
+        // read it, and forest surveys are not written to while being analyzed.
+        let mmap = unsafe { Mmap::map(&file)? };
+        let first_line_end = mmap
+            .iter()
+            .position(|byte| *byte == b'\n')
+            .unwrap_or(mmap.len());
+        let width = if first_line_end > 0 && mmap[first_line_end - 1] == b'\r' {
+            first_line_end - 1
+        } else {
+            first_line_end
+        };
+        let stride = first_line_end + 1;
+        let line_break = &mmap[width..stride.min(mmap.len())];
+        if width == 0 {
+            return Err(io::Error::new(ErrorKind::InvalidData, "forest is empty"));
+        }
+        let invalid_rows = || {
+            io::Error::new(
+                ErrorKind::InvalidData,
+                "forest rows must consist of digits and have the same length",
+            )
+        };
+        // like `parse_map`, trailing line breaks (and thus blank lines) are
+        // ignored; the last line must be as long as all the others
+        let content_len = mmap.len()
+            - mmap
+                .iter()
+                .rev()
+                .take_while(|byte| matches!(byte, b'\n' | b'\r'))
+                .count();
+        let height = if (content_len + stride - width) % stride == 0 {
+            (content_len + stride - width) / stride
+        } else {
+            return Err(invalid_rows());
+        };
+        if (0..height).any(|row| {
+            let start = row * stride;
+            let line = &mmap[start..start + width];
+            let end = (start + stride).min(content_len);
+            !line.iter().all(u8::is_ascii_digit)
+                || !line_break.starts_with(&mmap[start + width..end])
+        }) {
+            return Err(invalid_rows());
+        }
+        Ok(Self {
+            mmap,
+            width,
+            height,
+            stride,
+        })
+    }
+
+    fn row(&self, row: usize) -> &[u8] {
+        &self.mmap[row * self.stride..row * self.stride + self.width]
+    }
+
+    /// Marks the trees of a single row that are visible from the left or from
+    /// the right. `suffix_max` is scratch space of the length of one row.
+    fn visible_within_row(row: &[u8], suffix_max: &mut [i16], visible: &mut [bool]) {
+        let mut max = -1;
+        for column in (0..row.len()).rev() {
+            suffix_max[column] = max;
+            max = max.max(row[column] as i16);
+        }
+        let mut max = -1;
+        for column in 0..row.len() {
+            let height = row[column] as i16;
+            visible[column] = height > max || height > suffix_max[column];
+            max = max.max(height);
+        }
+    }
+
+    /// Counts the trees that are visible from outside of the forest without
+    /// copying the map. The first pass goes top-down and handles the views
+    /// from the top, left and right. The second pass goes bottom-up and adds
+    /// the trees that are only visible from the bottom. Besides a few buffers
+    /// of the length of one row, it only keeps the rows at which the
+    /// running maximum of each column increases, which happens at most ten
+    /// times per column.
+    pub fn count_visible(&self) -> usize {
+        let mut count = 0;
+        let mut suffix_max = vec![0; self.width];
+        let mut visible = vec![false; self.width];
+        let mut column_max = vec![-1i16; self.width];
+        let mut top_records: Vec<Vec<usize>> = vec![Vec::new(); self.width];
+
+        for row_index in 0..self.height {
+            let row = self.row(row_index);
+            Self::visible_within_row(row, &mut suffix_max, &mut visible);
+            for column in 0..self.width {
+                let height = row[column] as i16;
+                if height > column_max[column] {
+                    column_max[column] = height;
+                    top_records[column].push(row_index);
+                    visible[column] = true;
+                }
+                if visible[column] {
+                    count += 1;
+                }
+            }
+        }
+
+        column_max.fill(-1);
+        for row_index in (0..self.height).rev() {
+            let row = self.row(row_index);
+            Self::visible_within_row(row, &mut suffix_max, &mut visible);
+            for column in 0..self.width {
+                let height = row[column] as i16;
+                if height > column_max[column] {
+                    column_max[column] = height;
+                    if !visible[column] && !top_records[column].contains(&row_index) {
+                        count += 1;
+                    }
+                }
+            }
+        }
+        count
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+
+    use super::MappedForest;
+
+    /// Writes `contents` to a temporary file and counts its visible trees.
+    fn count_visible(name: &str, contents: &str) -> io::Result<usize> {
+        let path = std::env::temp_dir().join(format!("aoc08-{}-{name}", std::process::id()));
+        std::fs::write(&path, contents)?;
+        let result = MappedForest::open(&path).map(|forest| forest.count_visible());
+        std::fs::remove_file(&path)?;
+        result
+    }
+
+    #[test]
+    fn line_breaks() {
+        let example = "30373\n25512\n65332\n33549\n35390\n";
+        assert_eq!(count_visible("lf", example).unwrap(), 21);
+        assert_eq!(
+            count_visible("no-final-lf", example.trim_end()).unwrap(),
+            21
+        );
+        let crlf = example.replace('\n', "\r\n");
+        assert_eq!(count_visible("crlf", &crlf).unwrap(), 21);
+        assert_eq!(count_visible("no-final-crlf", crlf.trim_end()).unwrap(), 21);
+        assert_eq!(count_visible("single-row", "123").unwrap(), 3);
+        assert_eq!(count_visible("single-row-lf", "123\n").unwrap(), 3);
+        // a trailing blank line, which `parse_map` accepts as well
+        let blank = format!("{example}\n");
+        assert_eq!(count_visible("trailing-blank-lf", &blank).unwrap(), 21);
+        assert_eq!(
+            count_visible("trailing-blank-lines-lf", &format!("{blank}\n\n")).unwrap(),
+            21
+        );
+        let blank = format!("{crlf}\r\n");
+        assert_eq!(count_visible("trailing-blank-crlf", &blank).unwrap(), 21);
+    }
+
+    #[test]
+    fn rows_of_different_lengths() {
+        assert!(count_visible("short-last-row", "1234\n5678\n12\n").is_err());
+        assert!(count_visible("truncated-last-row", "1234\n5678\n12").is_err());
+        assert!(count_visible("long-row", "12\n345\n67\n").is_err());
+        assert!(count_visible("not-digits", "12\n3x\n").is_err());
+        assert!(count_visible("empty", "").is_err());
+    }
+}
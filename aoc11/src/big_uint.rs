@@ -0,0 +1,306 @@
+use std::{cmp::Ordering, fmt::Display};
+
+/// An arbitrary-precision unsigned integer, stored as little-endian 32 bit
+/// limbs without leading zero limbs (zero has no limbs at all).
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) struct BigUint {
+    limbs: Vec<u32>,
+}
+
+impl From<u64> for BigUint {
+    fn from(value: u64) -> Self {
+        let mut result = Self {
+            limbs: vec![value as u32, (value >> 32) as u32],
+        };
+        result.normalize();
+        result
+    }
+}
+
+impl PartialOrd for BigUint {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for BigUint {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.limbs
+            .len()
+            .cmp(&other.limbs.len())
+            .then_with(|| self.limbs.iter().rev().cmp(other.limbs.iter().rev()))
+    }
+}
+
+impl BigUint {
+    fn normalize(&mut self) {
+        while self.limbs.last() == Some(&0) {
+            self.limbs.pop();
+        }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.limbs.is_empty()
+    }
+
+    /// Returns the value if it fits into a `u64`.
+    pub fn to_u64(&self) -> Option<u64> {
+        match self.limbs[..] {
+            [] => Some(0),
+            [low] => Some(low as u64),
+            [low, high] => Some((high as u64) << 32 | low as u64),
+            _ => None,
+        }
+    }
+
+    pub fn add(&self, rhs: &Self) -> Self {
+        let mut limbs = Vec::with_capacity(self.limbs.len().max(rhs.limbs.len()) + 1);
+        let mut carry = 0u64;
+        for i in 0..self.limbs.len().max(rhs.limbs.len()) {
+            let sum = *self.limbs.get(i).unwrap_or(&0) as u64
+                + *rhs.limbs.get(i).unwrap_or(&0) as u64
+                + carry;
+            limbs.push(sum as u32);
+            carry = sum >> 32;
+        }
+        limbs.push(carry as u32);
+        let mut result = Self { limbs };
+        result.normalize();
+        result
+    }
+
+    /// Returns `None` if `rhs` is greater than `self`.
+    pub fn sub(&self, rhs: &Self) -> Option<Self> {
+        if *self < *rhs {
+            return None;
+        }
+        let mut limbs = Vec::with_capacity(self.limbs.len());
+        let mut borrow = 0i64;
+        for i in 0..self.limbs.len() {
+            let mut difference =
+                self.limbs[i] as i64 - *rhs.limbs.get(i).unwrap_or(&0) as i64 - borrow;
+            borrow = 0;
+            if difference < 0 {
+                difference += 1 << 32;
+                borrow = 1;
+            }
+            limbs.push(difference as u32);
+        }
+        let mut result = Self { limbs };
+        result.normalize();
+        Some(result)
+    }
+
+    pub fn mul(&self, rhs: &Self) -> Self {
+        if self.is_zero() || rhs.is_zero() {
+            return Self::default();
+        }
+        let mut limbs = vec![0u32; self.limbs.len() + rhs.limbs.len()];
+        for (i, lhs_limb) in self.limbs.iter().enumerate() {
+            let mut carry = 0u64;
+            for (j, rhs_limb) in rhs.limbs.iter().enumerate() {
+                let product = *lhs_limb as u64 * *rhs_limb as u64 + limbs[i + j] as u64 + carry;
+                limbs[i + j] = product as u32;
+                carry = product >> 32;
+            }
+            limbs[i + rhs.limbs.len()] = carry as u32;
+        }
+        let mut result = Self { limbs };
+        result.normalize();
+        result
+    }
+
+    /// Divides by a single limb, returning the quotient and the remainder.
+    fn div_rem_limb(&self, divisor: u32) -> (Self, u32) {
+        let mut limbs = vec![0u32; self.limbs.len()];
+        let mut remainder = 0u64;
+        for i in (0..self.limbs.len()).rev() {
+            let current = (remainder << 32) | self.limbs[i] as u64;
+            limbs[i] = (current / divisor as u64) as u32;
+            remainder = current % divisor as u64;
+        }
+        let mut quotient = Self { limbs };
+        quotient.normalize();
+        (quotient, remainder as u32)
+    }
+
+    fn bit_length(&self) -> usize {
+        match self.limbs.last() {
+            Some(last) => self.limbs.len() * 32 - last.leading_zeros() as usize,
+            None => 0,
+        }
+    }
+
+    fn bit(&self, index: usize) -> bool {
+        self.limbs
+            .get(index / 32)
+            .is_some_and(|limb| limb >> (index % 32) & 1 == 1)
+    }
+
+    fn shift_left_one_bit_with(&mut self, lowest_bit: bool) {
+        let mut carry = lowest_bit as u32;
+        for limb in &mut self.limbs {
+            let next_carry = *limb >> 31;
+            *limb = (*limb << 1) | carry;
+            carry = next_carry;
+        }
+        if carry != 0 {
+            self.limbs.push(carry);
+        }
+    }
+
+    /// Returns the quotient and the remainder, or `None` when dividing by zero.
+    pub fn div_rem(&self, divisor: &Self) -> Option<(Self, Self)> {
+        match divisor.limbs.len() {
+            0 => None,
+            1 => {
+                let (quotient, remainder) = self.div_rem_limb(divisor.limbs[0]);
+                Some((quotient, Self::from(remainder as u64)))
+            }
+            _ => {
+                // schoolbook binary long division
+                let mut quotient = Self {
+                    limbs: vec![0; self.limbs.len()],
+                };
+                let mut remainder = Self::default();
+                for bit in (0..self.bit_length()).rev() {
+                    remainder.shift_left_one_bit_with(self.bit(bit));
+                    if remainder >= *divisor {
+                        remainder = remainder.sub(divisor).unwrap();
+                        quotient.limbs[bit / 32] |= 1 << (bit % 32);
+                    }
+                }
+                quotient.normalize();
+                Some((quotient, remainder))
+            }
+        }
+    }
+}
+
+impl Display for BigUint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.is_zero() {
+            return f.write_str("0");
+        }
+        // split into chunks of nine decimal digits, least significant first
+        const CHUNK: u32 = 1_000_000_000;
+        let mut chunks = Vec::new();
+        let mut rest = self.clone();
+        while !rest.is_zero() {
+            let (quotient, remainder) = rest.div_rem_limb(CHUNK);
+            chunks.push(remainder);
+            rest = quotient;
+        }
+        f.write_fmt(format_args!("{}", chunks.last().unwrap()))?;
+        for chunk in chunks.iter().rev().skip(1) {
+            f.write_fmt(format_args!("{chunk:09}"))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BigUint;
+
+    fn big(value: u128) -> BigUint {
+        BigUint::from((value >> 64) as u64)
+            .mul(&BigUint::from(1 << 32))
+            .mul(&BigUint::from(1 << 32))
+            .add(&BigUint::from(value as u64))
+    }
+
+    /// Values around the limb boundaries, including zero.
+    fn samples() -> Vec<u128> {
+        let mut samples = vec![
+            0,
+            1,
+            2,
+            3,
+            7,
+            10,
+            999_999_999,
+            1_000_000_000,
+            u32::MAX as u128 - 1,
+            u32::MAX as u128,
+            1 << 32,
+            (1 << 32) + 1,
+            0x1234_5678_9abc_def0,
+            u64::MAX as u128,
+            1 << 64,
+            (1 << 64) + 1,
+            0xdead_beef_0000_0001_cafe_babe,
+            1 << 96,
+            u128::MAX / 3,
+            u128::MAX - 1,
+            u128::MAX,
+        ];
+        // a few pseudo random values of all lengths
+        let mut state = 0x2545_f491_4f6c_dd1du128;
+        for bits in [8, 31, 33, 47, 63, 65, 80, 95, 97, 127] {
+            state = state.wrapping_mul(0x5851_f42d_4c95_7f2d).wrapping_add(1);
+            samples.push(state >> (128 - bits));
+        }
+        samples
+    }
+
+    #[test]
+    fn limbs_are_normalized() {
+        assert_eq!(big(0), BigUint::default());
+        assert!(big(0).is_zero());
+        assert_eq!(big(5).sub(&big(5)), Some(BigUint::default()));
+        assert_eq!(big(1 << 64).mul(&big(0)), BigUint::default());
+    }
+
+    #[test]
+    fn add_and_sub() {
+        for a in samples() {
+            for b in samples() {
+                if let Some(sum) = a.checked_add(b) {
+                    assert_eq!(big(a).add(&big(b)), big(sum), "{a} + {b}");
+                }
+                assert_eq!(big(a).sub(&big(b)), a.checked_sub(b).map(big), "{a} - {b}");
+            }
+        }
+    }
+
+    #[test]
+    fn mul() {
+        for a in samples() {
+            for b in samples() {
+                let product = big(a).mul(&big(b));
+                match a.checked_mul(b) {
+                    Some(expected) => assert_eq!(product, big(expected), "{a} * {b}"),
+                    // check products that do not fit into a u128 by dividing
+                    None => assert_eq!(product.div_rem(&big(b)), Some((big(a), big(0)))),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn div_rem() {
+        for a in samples() {
+            for b in samples() {
+                let expected = a.checked_div(b).map(|quotient| (big(quotient), big(a % b)));
+                assert_eq!(big(a).div_rem(&big(b)), expected, "{a} / {b}");
+            }
+        }
+    }
+
+    #[test]
+    fn display() {
+        for a in samples() {
+            assert_eq!(big(a).to_string(), a.to_string());
+        }
+        let ten_to_the_40 = (0..40).fold(big(1), |value, _| value.mul(&big(10)));
+        assert_eq!(ten_to_the_40.to_string(), format!("1{}", "0".repeat(40)));
+    }
+
+    #[test]
+    fn to_u64() {
+        for a in samples() {
+            assert_eq!(big(a).to_u64(), u64::try_from(a).ok());
+        }
+    }
+}
@@ -0,0 +1,215 @@
+use crate::{
+    parser::{Expression, Operator},
+    worry_level::{ArithmeticError, WorryLevel},
+};
+
+/// Upper bound for the stack depth of compiled bytecode. Deeper expressions are
+/// evaluated by walking the expression tree instead.
+const MAX_STACK_DEPTH: usize = 16;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Instruction {
+    PushOld,
+    PushInteger(u64),
+    Apply(Operator),
+}
+
+/// An operation compiled into a form that is cheap to evaluate. The shapes
+/// that appear in the puzzle inputs get dedicated variants, everything else is
+/// turned into stack bytecode.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum CompiledOperation {
+    /// `old + n`
+    Add(u64),
+    /// `old * n`
+    Multiply(u64),
+    /// `old * old`
+    Square,
+    Bytecode(Vec<Instruction>),
+    Tree(Expression),
+}
+
+fn emit(expression: &Expression, code: &mut Vec<Instruction>) {
+    match expression {
+        Expression::Integer(value) => code.push(Instruction::PushInteger(*value)),
+        Expression::Old => code.push(Instruction::PushOld),
+        Expression::Binary { operator, lhs, rhs } => {
+            emit(lhs, code);
+            emit(rhs, code);
+            code.push(Instruction::Apply(*operator));
+        }
+    }
+}
+
+fn stack_depth(code: &[Instruction]) -> usize {
+    let mut depth = 0usize;
+    let mut max_depth = 0;
+    for instruction in code {
+        match instruction {
+            Instruction::PushOld | Instruction::PushInteger(_) => depth += 1,
+            Instruction::Apply(_) => depth -= 1,
+        }
+        max_depth = max_depth.max(depth);
+    }
+    max_depth
+}
+
+impl CompiledOperation {
+    pub fn compile(expression: &Expression) -> Self {
+        if let Expression::Binary { operator, lhs, rhs } = expression {
+            match (operator, lhs.as_ref(), rhs.as_ref()) {
+                (Operator::Add, Expression::Old, Expression::Integer(value))
+                | (Operator::Add, Expression::Integer(value), Expression::Old) => {
+                    return CompiledOperation::Add(*value)
+                }
+                (Operator::Multiply, Expression::Old, Expression::Integer(value))
+                | (Operator::Multiply, Expression::Integer(value), Expression::Old) => {
+                    return CompiledOperation::Multiply(*value)
+                }
+                (Operator::Multiply, Expression::Old, Expression::Old) => {
+                    return CompiledOperation::Square
+                }
+                (Operator::Add, Expression::Old, Expression::Old) => {
+                    return CompiledOperation::Multiply(2)
+                }
+                _ => {}
+            }
+        }
+        let mut code = Vec::new();
+        emit(expression, &mut code);
+        if stack_depth(&code) <= MAX_STACK_DEPTH {
+            CompiledOperation::Bytecode(code)
+        } else {
+            CompiledOperation::Tree(expression.clone())
+        }
+    }
+
+    pub fn evaluate<W: WorryLevel>(&self, old_value: &W) -> Result<W, ArithmeticError> {
+        match self {
+            CompiledOperation::Add(value) => old_value.checked_add(&W::from_u64(*value)),
+            CompiledOperation::Multiply(value) => old_value.checked_mul(&W::from_u64(*value)),
+            CompiledOperation::Square => old_value.checked_mul(old_value),
+            CompiledOperation::Bytecode(code) => {
+                let mut stack: [W; MAX_STACK_DEPTH] = Default::default();
+                let mut top = 0;
+                for instruction in code {
+                    match instruction {
+                        Instruction::PushOld => {
+                            stack[top] = old_value.clone();
+                            top += 1;
+                        }
+                        Instruction::PushInteger(value) => {
+                            stack[top] = W::from_u64(*value);
+                            top += 1;
+                        }
+                        Instruction::Apply(operator) => {
+                            top -= 1;
+                            stack[top - 1] = operator.apply(&stack[top - 1], &stack[top])?;
+                        }
+                    }
+                }
+                Ok(std::mem::take(&mut stack[0]))
+            }
+            CompiledOperation::Tree(expression) => expression.evaluate(old_value),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CompiledOperation, MAX_STACK_DEPTH};
+    use crate::{
+        big_uint::BigUint,
+        lexer::tokenize,
+        parser::{parse_operation, Expression},
+        worry_level::ArithmeticError,
+    };
+
+    fn expression(operation: &str) -> Expression {
+        parse_operation(tokenize(operation).unwrap()).unwrap()
+    }
+
+    /// Compiles the operation and checks that it gives the same results as
+    /// the expression tree, including the errors, for small worry levels and
+    /// some close to the limits of `u64`.
+    fn compile(operation: &str) -> CompiledOperation {
+        let expression = expression(operation);
+        let compiled = CompiledOperation::compile(&expression);
+        for old in (0..100).chain(u64::MAX - 100..=u64::MAX) {
+            assert_eq!(
+                compiled.evaluate(&old),
+                expression.evaluate(&old),
+                "{operation} with old = {old}"
+            );
+            assert_eq!(
+                compiled.evaluate(&(old as u128)),
+                expression.evaluate(&(old as u128)),
+                "{operation} with old = {old}"
+            );
+            assert_eq!(
+                compiled.evaluate(&BigUint::from(old)),
+                expression.evaluate(&BigUint::from(old)),
+                "{operation} with old = {old}"
+            );
+        }
+        compiled
+    }
+
+    #[test]
+    fn fast_paths() {
+        assert_eq!(compile("new = old + 6"), CompiledOperation::Add(6));
+        assert_eq!(compile("new = 6 + old"), CompiledOperation::Add(6));
+        assert_eq!(compile("new = old * 19"), CompiledOperation::Multiply(19));
+        assert_eq!(compile("new = 19 * old"), CompiledOperation::Multiply(19));
+        assert_eq!(compile("new = old * old"), CompiledOperation::Square);
+        assert_eq!(compile("new = old + old"), CompiledOperation::Multiply(2));
+    }
+
+    #[test]
+    fn bytecode() {
+        for operation in [
+            "new = (old + 2) * (old - 1) / 3 % 7",
+            "new = old - old",
+            "new = 7",
+            "new = old",
+            "new = old * 2 + old * old * 3",
+        ] {
+            assert!(matches!(compile(operation), CompiledOperation::Bytecode(_)));
+        }
+    }
+
+    #[test]
+    fn deep_expressions_fall_back_to_the_tree() {
+        let deep = |depth| format!("new = {}old{}", "1 + (".repeat(depth), ")".repeat(depth));
+        assert!(matches!(
+            compile(&deep(MAX_STACK_DEPTH - 1)),
+            CompiledOperation::Bytecode(_)
+        ));
+        assert!(matches!(
+            compile(&deep(MAX_STACK_DEPTH)),
+            CompiledOperation::Tree(_)
+        ));
+        let deep = format!("new = {}old - 5{}", "2 * (".repeat(20), ")".repeat(20));
+        assert!(matches!(compile(&deep), CompiledOperation::Tree(_)));
+    }
+
+    #[test]
+    fn errors() {
+        let compiled = compile("new = old - 5");
+        assert_eq!(compiled.evaluate(&3u64), Err(ArithmeticError::Underflow));
+        let compiled = compile("new = 100 / old");
+        assert_eq!(
+            compiled.evaluate(&0u64),
+            Err(ArithmeticError::DivisionByZero)
+        );
+        let compiled = compile("new = 100 % (old - 1)");
+        assert_eq!(
+            compiled.evaluate(&1u64),
+            Err(ArithmeticError::DivisionByZero)
+        );
+        let compiled = compile("new = old * 18446744073709551615");
+        assert_eq!(compiled.evaluate(&2u64), Err(ArithmeticError::Overflow));
+        let compiled = compile("new = old * old");
+        assert_eq!(compiled.evaluate(&u64::MAX), Err(ArithmeticError::Overflow));
+    }
+}
@@ -0,0 +1,170 @@
+use std::collections::HashMap;
+
+use crate::{worry_level::ArithmeticError, Monkey, WorryLevel};
+
+/// Where an item is at the beginning of a round: the monkey holding it and its
+/// worry level modulo the reduction modulus.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+struct ItemState {
+    monkey: usize,
+    worry_level: u64,
+}
+
+/// Follows a single item through one round and appends the monkeys inspecting
+/// it to `inspectors`. The item keeps moving within the round as long as it is
+/// thrown to a monkey that has not had its turn yet.
+fn advance_round(
+    monkeys: &[Monkey],
+    modulus: u64,
+    state: ItemState,
+    inspectors: &mut Vec<usize>,
+) -> Result<ItemState, ArithmeticError> {
+    let ItemState {
+        mut monkey,
+        mut worry_level,
+    } = state;
+    loop {
+        let current = &monkeys[monkey];
+        inspectors.push(monkey);
+        worry_level =
+            WorryLevel::checked_rem(&current.operation.evaluate(&worry_level)?, &modulus)?;
+        let target = if worry_level.is_divisible_by(current.test_divisor)? {
+            current.target_monkey_if_true
+        } else {
+            current.target_monkey_if_false
+        } as usize;
+        if target <= monkey {
+            return Ok(ItemState {
+                monkey: target,
+                worry_level,
+            });
+        }
+        monkey = target;
+    }
+}
+
+/// Adds `times` inspections to every monkey in `inspectors`.
+fn add_inspections(
+    inspections: &mut [u64],
+    inspectors: &[usize],
+    times: u64,
+) -> Result<(), ArithmeticError> {
+    for &monkey in inspectors {
+        inspections[monkey] = inspections[monkey]
+            .checked_add(times)
+            .ok_or(ArithmeticError::Overflow)?;
+    }
+    Ok(())
+}
+
+/// Counts how often each monkey inspects an item within `num_rounds` rounds,
+/// with worry levels reduced modulo `modulus` after every inspection. The
+/// counts start from the inspections the monkeys have already made, as read
+/// from a snapshot.
+///
+/// Items never influence each other, so each one is followed on its own. Its
+/// state at the beginning of a round can only take finitely many values, so its
+/// trajectory eventually repeats. Once the cycle is found, the inspections of
+/// all remaining full cycles are added up at once instead of simulating them.
+pub(crate) fn count_inspections(
+    monkeys: &[Monkey],
+    modulus: u64,
+    num_rounds: u64,
+) -> Result<Vec<u64>, ArithmeticError> {
+    let mut total: Vec<u64> = monkeys
+        .iter()
+        .map(|monkey| monkey.number_of_inspections)
+        .collect();
+    for (monkey, worry_level) in monkeys.iter().enumerate().flat_map(|(monkey, current)| {
+        current
+            .items
+            .iter()
+            .map(move |item| (monkey, item.worry_level))
+    }) {
+        let mut state = ItemState {
+            monkey,
+            worry_level: worry_level % modulus,
+        };
+        // the monkeys that inspected the item, in order, and where in that
+        // list each round starts
+        let mut inspectors = Vec::new();
+        let mut round_starts = vec![0];
+        let mut first_seen = HashMap::from([(state, 0u64)]);
+        for round in 1..=num_rounds {
+            state = advance_round(monkeys, modulus, state, &mut inspectors)?;
+            round_starts.push(inspectors.len());
+            if let Some(cycle_start) = first_seen.insert(state, round) {
+                let cycle_length = round - cycle_start;
+                let remaining_rounds = num_rounds - round;
+                let cycle = &inspectors[round_starts[cycle_start as usize]..];
+                let leftover =
+                    round_starts[(cycle_start + remaining_rounds % cycle_length) as usize];
+                add_inspections(&mut total, cycle, remaining_rounds / cycle_length)?;
+                add_inspections(
+                    &mut total,
+                    &inspectors[round_starts[cycle_start as usize]..leftover],
+                    1,
+                )?;
+                break;
+            }
+        }
+        add_inspections(&mut total, &inspectors, 1)?;
+    }
+    Ok(total)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::count_inspections;
+    use crate::{
+        inspection_counts, make_round, notes, relief::Relief, tests::EXAMPLE, trace::NoTrace,
+        worry_level::ArithmeticError, worry_modulus,
+    };
+
+    #[test]
+    fn agrees_with_the_simulation() {
+        let monkeys = notes::parse_input(EXAMPLE).unwrap();
+        let modulus = worry_modulus::<u64>(&monkeys).unwrap();
+        let relief = Relief::Modulo(modulus);
+        let mut simulated = notes::parse_input(EXAMPLE).unwrap();
+        for num_rounds in 0..=10000 {
+            if num_rounds <= 200 || num_rounds % 1000 == 0 {
+                assert_eq!(
+                    count_inspections(&monkeys, modulus, num_rounds),
+                    Ok(inspection_counts(&simulated)),
+                    "after {num_rounds} rounds"
+                );
+            }
+            make_round(&mut simulated, &relief, &mut NoTrace).unwrap();
+        }
+    }
+
+    #[test]
+    fn resumes_from_a_snapshot() {
+        let monkeys = notes::parse_input(EXAMPLE).unwrap();
+        let modulus = worry_modulus::<u64>(&monkeys).unwrap();
+        let relief = Relief::Modulo(modulus);
+        let mut simulated = notes::parse_input(EXAMPLE).unwrap();
+        for _ in 0..5 {
+            make_round(&mut simulated, &relief, &mut NoTrace).unwrap();
+        }
+        let snapshot = notes::parse_input(&notes::serialize(&simulated).unwrap()).unwrap();
+        for _ in 0..5 {
+            make_round(&mut simulated, &relief, &mut NoTrace).unwrap();
+        }
+        assert_eq!(
+            count_inspections(&snapshot, modulus, 5),
+            Ok(inspection_counts(&simulated))
+        );
+    }
+
+    #[test]
+    fn too_many_rounds() {
+        let monkeys = notes::parse_input(EXAMPLE).unwrap();
+        let modulus = worry_modulus::<u64>(&monkeys).unwrap();
+        assert_eq!(
+            count_inspections(&monkeys, modulus, u64::MAX),
+            Err(ArithmeticError::Overflow)
+        );
+    }
+}
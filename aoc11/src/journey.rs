@@ -0,0 +1,144 @@
+use std::collections::BTreeMap;
+
+use crate::{
+    trace::{Event, Observer},
+    Monkey, WorryLevel,
+};
+
+/// Where an item was at some point of its journey.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Step<W> {
+    /// The round in which the item arrived, 0 for its starting monkey.
+    pub(crate) round: u64,
+    pub(crate) monkey: usize,
+    /// The worry level the item had when it arrived.
+    pub(crate) worry_level: W,
+}
+
+/// Records the journey of every item through the monkeys, by item id. Ids
+/// need not be dense, since snapshots keep the ids of the original notes.
+pub(crate) struct JourneyTracker<W> {
+    journeys: BTreeMap<usize, Vec<Step<W>>>,
+    completed_rounds: u64,
+}
+
+impl<W: WorryLevel> JourneyTracker<W> {
+    /// Starts a journey for every item the monkeys hold at the beginning.
+    pub fn new(monkeys: &[Monkey]) -> Self {
+        let mut journeys = BTreeMap::new();
+        for (monkey, current) in monkeys.iter().enumerate() {
+            for item in &current.items {
+                journeys.insert(
+                    item.id,
+                    vec![Step {
+                        round: 0,
+                        monkey,
+                        worry_level: W::from_u64(item.worry_level),
+                    }],
+                );
+            }
+        }
+        Self {
+            journeys,
+            completed_rounds: 0,
+        }
+    }
+}
+
+impl<W> JourneyTracker<W> {
+    /// Returns every monkey the item visited in order, starting with the one
+    /// that held it at the beginning. Returns `None` if there is no such item.
+    pub fn journey(&self, item: usize) -> Option<&[Step<W>]> {
+        self.journeys
+            .get(&item)
+            .map(Vec::as_slice)
+            .filter(|journey| !journey.is_empty())
+    }
+
+    /// How often an item changes hands per round, averaged over all items.
+    pub fn average_hops_per_round(&self) -> f64 {
+        let hops: usize = self
+            .journeys
+            .values()
+            .map(|journey| journey.len().saturating_sub(1))
+            .sum();
+        let item_rounds = self.journeys.len() as f64 * self.completed_rounds as f64;
+        if item_rounds == 0.0 {
+            0.0
+        } else {
+            hops as f64 / item_rounds
+        }
+    }
+}
+
+impl<W: Clone> Observer<W> for JourneyTracker<W> {
+    fn observe(&mut self, event: &Event<'_, W>) {
+        match event {
+            Event::Throw {
+                item,
+                worry_level,
+                target_monkey,
+            } => {
+                if let Some(journey) = self.journeys.get_mut(item) {
+                    journey.push(Step {
+                        round: self.completed_rounds + 1,
+                        monkey: *target_monkey,
+                        worry_level: (*worry_level).clone(),
+                    });
+                }
+            }
+            Event::RoundEnd { round, .. } => self.completed_rounds = *round,
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{JourneyTracker, Step};
+    use crate::{notes, relief::Relief, simulate, tests::EXAMPLE};
+
+    /// Follows all items of the example through one round of part 1.
+    fn one_round(notes: &str) -> JourneyTracker<u64> {
+        let monkeys = notes::parse_input(notes).unwrap();
+        let mut tracker = JourneyTracker::new(&monkeys);
+        simulate::<u64>(monkeys, &Relief::DivideBy(3), 1, &mut tracker).unwrap();
+        tracker
+    }
+
+    #[test]
+    fn journey_of_one_item() {
+        let tracker = one_round(EXAMPLE);
+        // 79 * 19 / 3 = 500 goes to monkey 3, which throws 503 / 3 = 167 on
+        let step = |round, monkey, worry_level| Step {
+            round,
+            monkey,
+            worry_level,
+        };
+        assert_eq!(
+            tracker.journey(0),
+            Some(&[step(0, 0, 79), step(1, 3, 500), step(1, 1, 167)][..])
+        );
+        assert_eq!(tracker.journey(10), None);
+    }
+
+    #[test]
+    fn average_hops() {
+        // monkey 0 throws 2 items, monkey 1 throws 4, monkey 2 throws 3 and
+        // monkey 3 throws 5, so the 10 items change hands 14 times
+        assert_eq!(one_round(EXAMPLE).average_hops_per_round(), 1.4);
+    }
+
+    #[test]
+    fn sparse_item_ids() {
+        let notes = EXAMPLE
+            .replace("79, 98\n", "79, 98\n  Item ids: 0, 100000000000\n")
+            .replace("75, 74\n", "75, 74\n  Item ids: 2, 3, 4, 5\n")
+            .replace("60, 97\n", "60, 97\n  Item ids: 6, 7, 8\n")
+            .replace("items: 74\n", "items: 74\n  Item ids: 9\n");
+        let tracker = one_round(&notes);
+        assert_eq!(tracker.journey(1), None);
+        assert_eq!(tracker.journey(100000000000).map(<[_]>::len), Some(3));
+        assert_eq!(tracker.average_hops_per_round(), 1.4);
+    }
+}
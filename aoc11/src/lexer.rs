@@ -0,0 +1,133 @@
+use std::{error::Error, fmt::Display};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Token {
+    Integer(u64),
+    Plus,
+    Minus,
+    Asterisk,
+    Slash,
+    Percent,
+    LeftParenthesis,
+    RightParenthesis,
+    New,
+    Old,
+    Equals,
+    EndOfInput,
+}
+
+impl Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Token::Integer(value) = self {
+            f.write_fmt(format_args!("{value}"))
+        } else {
+            f.write_fmt(format_args!(
+                "{}",
+                match self {
+                    Token::Plus => "+",
+                    Token::Minus => "-",
+                    Token::Asterisk => "*",
+                    Token::Slash => "/",
+                    Token::Percent => "%",
+                    Token::LeftParenthesis => "(",
+                    Token::RightParenthesis => ")",
+                    Token::New => "new",
+                    Token::Old => "old",
+                    Token::Equals => "=",
+                    Token::EndOfInput => "end of input",
+                    _ => unreachable!(),
+                }
+            ))
+        }
+    }
+}
+
+/// A token together with the 1-based column at which it starts.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct Lexeme {
+    pub(crate) token: Token,
+    pub(crate) column: usize,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum LexerErrorKind {
+    UnexpectedCharacter(char),
+    UnknownIdentifier(String),
+    IntegerOutOfRange(String),
+}
+
+/// An error in an operation. `column` is 1-based and counts characters.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct LexerError {
+    pub(crate) column: usize,
+    pub(crate) kind: LexerErrorKind,
+}
+
+impl Display for LexerError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_fmt(format_args!("column {}: ", self.column))?;
+        match &self.kind {
+            LexerErrorKind::UnexpectedCharacter(c) => {
+                f.write_fmt(format_args!("unexpected character {c:?}"))
+            }
+            LexerErrorKind::UnknownIdentifier(word) => {
+                f.write_fmt(format_args!("unknown identifier \"{word}\""))
+            }
+            LexerErrorKind::IntegerOutOfRange(word) => {
+                f.write_fmt(format_args!("integer {word} does not fit into 64 bits"))
+            }
+        }
+    }
+}
+
+impl Error for LexerError {}
+
+pub(crate) fn tokenize(input: &str) -> Result<Vec<Lexeme>, LexerError> {
+    let mut result = Vec::new();
+    let mut chars = input.chars().enumerate().peekable();
+    while let Some((index, c)) = chars.next() {
+        let column = index + 1;
+        let error = |kind| LexerError { column, kind };
+        let token = match c {
+            '+' => Token::Plus,
+            '-' => Token::Minus,
+            '*' => Token::Asterisk,
+            '/' => Token::Slash,
+            '%' => Token::Percent,
+            '(' => Token::LeftParenthesis,
+            ')' => Token::RightParenthesis,
+            '=' => Token::Equals,
+            c if c.is_whitespace() => continue,
+            c if c.is_ascii_digit() => {
+                let mut digits = c.to_string();
+                while let Some((_, next)) = chars.next_if(|(_, next)| next.is_ascii_digit()) {
+                    digits.push(next);
+                }
+                Token::Integer(
+                    digits
+                        .parse()
+                        .map_err(|_| error(LexerErrorKind::IntegerOutOfRange(digits.clone())))?,
+                )
+            }
+            c if c.is_ascii_alphabetic() => {
+                let mut word = c.to_string();
+                while let Some((_, next)) = chars.next_if(|(_, next)| next.is_ascii_alphanumeric())
+                {
+                    word.push(next);
+                }
+                match word.as_str() {
+                    "new" => Token::New,
+                    "old" => Token::Old,
+                    _ => return Err(error(LexerErrorKind::UnknownIdentifier(word))),
+                }
+            }
+            c => return Err(error(LexerErrorKind::UnexpectedCharacter(c))),
+        };
+        result.push(Lexeme { token, column });
+    }
+    result.push(Lexeme {
+        token: Token::EndOfInput,
+        column: input.chars().count() + 1,
+    });
+    Ok(result)
+}
@@ -1,83 +1,67 @@
-use std::fmt::Display;
-
-enum Token {
-    Integer(u64),
-    Plus,
-    Asterisk,
-    New,
-    Old,
-    Equals,
-}
+use std::{error::Error, fmt::Display};
 
-impl Display for Token {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        if let Token::Integer(value) = self {
-            f.write_fmt(format_args!("{value}"))
-        } else {
-            f.write_fmt(format_args!(
-                "{}",
-                match self {
-                    Token::Plus => "+",
-                    Token::Asterisk => "*",
-                    Token::New => "new",
-                    Token::Old => "old",
-                    Token::Equals => "=",
-                    _ => unreachable!(),
-                }
-            ))
-        }
-    }
-}
+use compiler::CompiledOperation;
+use journey::JourneyTracker;
+use parser::Expression;
+use relief::{Relief, ReliefRule};
+use trace::{Event, NoTrace, Observer, TextTrace};
+use worry_level::{ArithmeticError, WorryLevel, WorryLevelMode};
+
+mod big_uint;
+mod compiler;
+mod cycles;
+mod journey;
+mod lexer;
+mod notes;
+mod parser;
+mod relief;
+mod trace;
+mod worry_level;
 
 struct Operation {
-    tokens: Vec<Token>,
+    expression: Expression,
+    compiled: CompiledOperation,
 }
 
 impl Operation {
-    pub fn from_string(string: &str) -> Self {
-        let parts = string.split(' ');
-        let tokens: Vec<_> = parts
-            .map(str::trim)
-            .map(|s| match s {
-                "+" => Token::Plus,
-                "*" => Token::Asterisk,
-                "=" => Token::Equals,
-                "new" => Token::New,
-                "old" => Token::Old,
-                _ => Token::Integer(s.parse().unwrap()),
-            })
-            .collect();
-        Self { tokens }
+    pub fn from_string(string: &str) -> Result<Self, Box<dyn Error>> {
+        let tokens = lexer::tokenize(string)?;
+        let expression = parser::parse_operation(tokens)?;
+        let compiled = CompiledOperation::compile(&expression);
+        Ok(Self {
+            expression,
+            compiled,
+        })
     }
 
-    pub fn evaluate(&self, old_value: u64) -> u64 {
-        assert!(matches!(self.tokens[0], Token::New));
-        assert!(matches!(self.tokens[1], Token::Equals));
-        assert!(matches!(self.tokens[2], Token::Old));
-        assert!(matches!(self.tokens[3], Token::Plus) || matches!(self.tokens[3], Token::Asterisk));
-        let lhs = old_value;
-        let rhs = match self.tokens[4] {
-            Token::Integer(value) => value,
-            Token::Old => old_value,
-            _ => unreachable!(),
-        };
-        match self.tokens[3] {
-            Token::Plus => lhs + rhs,
-            Token::Asterisk => lhs * rhs,
-            _ => unreachable!(),
-        }
+    pub fn evaluate<W: WorryLevel>(&self, old_value: &W) -> Result<W, ArithmeticError> {
+        self.compiled.evaluate(old_value)
+    }
+
+    /// Evaluates the operation by walking the expression tree, which is slower
+    /// than `evaluate` but does not rely on the compiled form.
+    pub fn interpret<W: WorryLevel>(&self, old_value: &W) -> Result<W, ArithmeticError> {
+        self.expression.evaluate(old_value)
     }
 }
 
 impl Display for Operation {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let tokens: Vec<_> = self.tokens.iter().map(|t| format!("{t}")).collect();
-        f.write_fmt(format_args!("{}", tokens.join(" ")))
+        f.write_fmt(format_args!("new = {}", self.expression))
     }
 }
 
-struct Monkey {
-    worry_levels: Vec<u64>,
+/// An item held by a monkey. Unless the notes give their ids, items are
+/// numbered in the order they appear in the notes, counting through the
+/// starting items of all monkeys.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Item<W = u64> {
+    id: usize,
+    worry_level: W,
+}
+
+struct Monkey<W = u64> {
+    items: Vec<Item<W>>,
     operation: Operation,
     test_divisor: u64,
     target_monkey_if_true: u64,
@@ -85,12 +69,40 @@ struct Monkey {
     number_of_inspections: u64,
 }
 
-impl Display for Monkey {
+impl Monkey {
+    /// Converts the worry levels of the items this monkey holds to another
+    /// number type.
+    fn with_worry_levels<W: WorryLevel>(self) -> Monkey<W> {
+        Monkey {
+            items: self
+                .items
+                .into_iter()
+                .map(|Item { id, worry_level }| Item {
+                    id,
+                    worry_level: W::from_u64(worry_level),
+                })
+                .collect(),
+            operation: self.operation,
+            test_divisor: self.test_divisor,
+            target_monkey_if_true: self.target_monkey_if_true,
+            target_monkey_if_false: self.target_monkey_if_false,
+            number_of_inspections: self.number_of_inspections,
+        }
+    }
+}
+
+/// Writes the notes of the monkey without the `Monkey N:` header, indented like
+/// the puzzle input.
+impl<W: Display> Display for Monkey<W> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let worry_levels: Vec<_> = self.worry_levels.iter().map(|n| format!("{n}")).collect();
+        let worry_levels: Vec<_> = self
+            .items
+            .iter()
+            .map(|item| format!(" {}", item.worry_level))
+            .collect();
         f.write_fmt(format_args!(
-            "Starting items: {}\nOperation: {}\nTest: divisible by {}\n  If true: throw to monkey {}\n  If false: throw to monkey {}\n",
-            worry_levels.join(", "),
+            "  Starting items:{}\n  Operation: {}\n  Test: divisible by {}\n    If true: throw to monkey {}\n    If false: throw to monkey {}\n",
+            worry_levels.join(","),
             self.operation,
             self.test_divisor,
             self.target_monkey_if_true,
@@ -99,160 +111,435 @@ impl Display for Monkey {
     }
 }
 
-#[must_use]
-fn parse_input(string: &str) -> Vec<Monkey> {
-    let lines: Vec<_> = string.lines().collect();
-    let mut monkeys = Vec::new();
-    for i in (0..lines.len()).step_by(7) {
-        let monkey = lines[i]
-            .split(' ')
-            .nth(1)
-            .unwrap()
-            .strip_suffix(':')
-            .unwrap()
-            .parse::<u64>()
-            .unwrap();
-        assert!(monkey == (i as u64) / 7);
-        let worry_levels: Vec<_> = lines[i + 1]
-            .split(':')
-            .last()
-            .unwrap()
-            .split(',')
-            .map(str::trim)
-            .map(str::parse::<u64>)
-            .map(Result::unwrap)
-            .collect();
-        let operation = Operation::from_string(lines[i + 2].split(':').last().unwrap().trim());
-        let get_trailing_number = |line_index: usize| {
-            lines[line_index]
-                .split(' ')
-                .last()
-                .unwrap()
-                .trim()
-                .parse::<u64>()
-                .unwrap()
-        };
-        let test_divisor = get_trailing_number(i + 3);
-        let target_monkey_when_true = get_trailing_number(i + 4);
-        let target_monkey_when_false = get_trailing_number(i + 5);
-        monkeys.push(Monkey {
-            worry_levels,
-            operation,
-            test_divisor,
-            target_monkey_if_true: target_monkey_when_true,
-            target_monkey_if_false: target_monkey_when_false,
-            number_of_inspections: 0,
-        });
-    }
-    monkeys
-}
-
-struct ThrowAction {
+struct ThrowAction<W> {
     target_monkey: usize,
-    worry_level: u64,
+    item: Item<W>,
 }
 
-fn prime_reduce(n: u64) -> u64 {
-    const PRIMES: &[u64] = &[2, 3, 5, 7, 11, 13, 17, 19, 23];
-    let product: u64 = PRIMES.iter().product();
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ModulusError {
+    ZeroDivisor {
+        monkey: usize,
+    },
+    /// The monkey's operation does something other than adding and
+    /// multiplying, which does not commute with taking the remainder.
+    IncompatibleOperation {
+        monkey: usize,
+    },
+    Overflow,
+}
 
-    n % product
-    /*let mut is_divisible = [false; PRIMES.len()];
-    for (i, prime) in PRIMES.iter().enumerate() {
-        is_divisible[i] = n % prime == 0;
+impl Display for ModulusError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ModulusError::ZeroDivisor { monkey } => {
+                f.write_fmt(format_args!("monkey {monkey} tests for divisibility by 0"))
+            }
+            ModulusError::IncompatibleOperation { monkey } => f.write_fmt(format_args!(
+                "the operation of monkey {monkey} does not only add and multiply, so worry levels cannot be reduced modulo the test divisors"
+            )),
+            ModulusError::Overflow => f.write_str(
+                "the least common multiple of all test divisors does not fit into a worry level",
+            ),
+        }
     }
+}
 
-    for prime in PRIMES {
-        while n % (prime * prime) == 0 {
-            n /= prime;
-        }
+impl Error for ModulusError {}
+
+fn greatest_common_divisor<W: WorryLevel>(mut a: W, mut b: W) -> W {
+    while !b.is_zero() {
+        let remainder = a.checked_rem(&b).expect("the divisor is not zero");
+        a = std::mem::replace(&mut b, remainder);
     }
-    n*/
+    a
 }
 
-#[derive(Clone, Copy, PartialEq, Eq)]
-enum Part {
-    Part1,
-    Part2,
+/// All tests only check for divisibility, so as long as every operation only
+/// adds and multiplies, worry levels can be reduced modulo the least common
+/// multiple of all test divisors without changing where any item is thrown.
+/// Subtraction, division and remainders would give different results on the
+/// reduced worry levels, so operations using them are rejected.
+fn worry_modulus<W: WorryLevel>(monkeys: &[Monkey]) -> Result<W, ModulusError> {
+    monkeys
+        .iter()
+        .enumerate()
+        .try_fold(W::from_u64(1), |modulus, (monkey, current)| {
+            if current.test_divisor == 0 {
+                return Err(ModulusError::ZeroDivisor { monkey });
+            }
+            if !current.operation.expression.only_adds_and_multiplies() {
+                return Err(ModulusError::IncompatibleOperation { monkey });
+            }
+            let divisor = W::from_u64(current.test_divisor);
+            let gcd = greatest_common_divisor(modulus.clone(), divisor.clone());
+            modulus
+                .checked_div(&gcd)
+                .and_then(|modulus| modulus.checked_mul(&divisor))
+                .map_err(|_| ModulusError::Overflow)
+        })
 }
 
-fn make_turn(monkeys: &mut [Monkey], monkey_index: usize, part: Part) {
+fn make_turn<W: WorryLevel>(
+    monkeys: &mut [Monkey<W>],
+    monkey_index: usize,
+    relief: &Relief<W>,
+    observer: &mut impl Observer<W>,
+) -> Result<(), ArithmeticError> {
+    observer.observe(&Event::Turn {
+        monkey: monkey_index,
+    });
     let monkey = &mut monkeys[monkey_index];
-    let mut to_throw = Vec::with_capacity(monkey.worry_levels.len());
-    for worry_level in &monkey.worry_levels {
-        //println!("  Monkey inspects an item with a worry level of {worry_level}.");
-        let new_worry_level = monkey.operation.evaluate(*worry_level);
-        //println!("    New worry level is {new_worry_level}.");
-
-        let new_worry_level = if part == Part::Part1 {
-            new_worry_level / 3
-        } else {
-            prime_reduce(new_worry_level)
-        };
+    let mut to_throw = Vec::with_capacity(monkey.items.len());
+    for Item { id, worry_level } in &monkey.items {
+        observer.observe(&Event::Inspection { worry_level });
+        let new_worry_level = monkey.operation.evaluate(worry_level)?;
+        observer.observe(&Event::Operation {
+            operation: &monkey.operation,
+            worry_level: &new_worry_level,
+        });
 
-        //println!("    Monkey gets bored => {new_worry_level}.");
+        let new_worry_level = relief.apply(new_worry_level)?;
+        observer.observe(&Event::Relief {
+            relief,
+            worry_level: &new_worry_level,
+        });
 
-        let target_monkey = if new_worry_level % monkey.test_divisor == 0 {
+        let divisible = new_worry_level.is_divisible_by(monkey.test_divisor)?;
+        observer.observe(&Event::Test {
+            divisor: monkey.test_divisor,
+            divisible,
+        });
+        let target_monkey = if divisible {
             monkey.target_monkey_if_true
         } else {
             monkey.target_monkey_if_false
-        };
-        /*println!(
-            "    Item with worry level {new_worry_level} is thrown to monkey {target_monkey}."
-        );*/
+        } as usize;
+        observer.observe(&Event::Throw {
+            item: *id,
+            worry_level: &new_worry_level,
+            target_monkey,
+        });
         to_throw.push(ThrowAction {
-            target_monkey: target_monkey as usize,
-            worry_level: new_worry_level,
+            target_monkey,
+            item: Item {
+                id: *id,
+                worry_level: new_worry_level,
+            },
         });
     }
-    monkey.number_of_inspections += monkey.worry_levels.len() as u64;
-    monkey.worry_levels.clear();
+    monkey.number_of_inspections += monkey.items.len() as u64;
+    monkey.items.clear();
     for throw_action in to_throw {
         let ThrowAction {
             target_monkey,
-            worry_level,
+            item,
         } = throw_action;
-        monkeys[target_monkey].worry_levels.push(worry_level);
+        monkeys[target_monkey].items.push(item);
     }
+    Ok(())
 }
 
-fn make_round(monkeys: &mut Vec<Monkey>, part: Part) {
+fn make_round<W: WorryLevel>(
+    monkeys: &mut [Monkey<W>],
+    relief: &Relief<W>,
+    observer: &mut impl Observer<W>,
+) -> Result<(), ArithmeticError> {
     for i in 0..monkeys.len() {
-        //println!("Monkey {i}:");
-        make_turn(monkeys, i, part);
+        make_turn(monkeys, i, relief, observer)?;
     }
+    Ok(())
 }
 
-fn main() {
-    let contents = std::fs::read_to_string("real_input.txt").unwrap();
-    let mut monkeys = parse_input(&contents);
-    /*for (i, monkey) in monkeys.iter().enumerate() {
-        println!("Monkey {i}:\n{monkey}");
-    }*/
-    const NUM_ROUNDS: usize = 10000;
-
-    for _ in 0..NUM_ROUNDS {
-        make_round(&mut monkeys, Part::Part2);
+/// Runs the simulation with worry levels stored as `W`, reporting every event
+/// to `observer`, and returns the monkeys after the last round.
+fn simulate<W: WorryLevel>(
+    monkeys: Vec<Monkey>,
+    relief: &Relief<W>,
+    num_rounds: u64,
+    observer: &mut impl Observer<W>,
+) -> Result<Vec<Monkey<W>>, ArithmeticError> {
+    let mut monkeys: Vec<Monkey<W>> = monkeys.into_iter().map(Monkey::with_worry_levels).collect();
+    for round in 1..=num_rounds {
+        make_round(&mut monkeys, relief, observer)?;
+        observer.observe(&Event::RoundEnd {
+            round,
+            monkeys: &monkeys,
+        });
     }
-    for (i, monkey) in monkeys.iter().enumerate() {
+    Ok(monkeys)
+}
+
+/// Returns how many items each monkey inspected.
+fn inspection_counts<W>(monkeys: &[Monkey<W>]) -> Vec<u64> {
+    monkeys
+        .iter()
+        .map(|monkey| monkey.number_of_inspections)
+        .collect()
+}
+
+/// Runs the simulation, narrating it on standard output if `trace` is set and
+/// following the item with the id `journey`, if any. The notes describing the
+/// monkeys after the last round are written to `snapshot`, if given.
+fn run<W: WorryLevel>(
+    monkeys: Vec<Monkey>,
+    relief_rule: ReliefRule,
+    num_rounds: u64,
+    trace: bool,
+    journey: Option<usize>,
+    snapshot: Option<&str>,
+) -> Result<Vec<u64>, Box<dyn Error>> {
+    let relief = &relief_rule.resolve::<W>(&monkeys)?;
+    let monkeys = if !trace && journey.is_none() {
+        simulate::<W>(monkeys, relief, num_rounds, &mut NoTrace)?
+    } else {
+        let text_trace = trace.then(|| TextTrace::new(std::io::stdout().lock()));
+        let tracker = journey.map(|_| JourneyTracker::<W>::new(&monkeys));
+        let mut observers = (text_trace, tracker);
+        let monkeys = simulate::<W>(monkeys, relief, num_rounds, &mut observers)?;
+        let (text_trace, tracker) = observers;
+        if let Some(text_trace) = text_trace {
+            text_trace.finish()?;
+        }
+        if let (Some(item), Some(tracker)) = (journey, tracker) {
+            print_journey(&tracker, item)?;
+        }
+        monkeys
+    };
+    if let Some(path) = snapshot {
+        std::fs::write(path, notes::serialize(&monkeys)?)?;
+    }
+    Ok(inspection_counts(&monkeys))
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct UnknownItem(usize);
+
+impl Display for UnknownItem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_fmt(format_args!("there is no item with the id {}", self.0))
+    }
+}
+
+impl Error for UnknownItem {}
+
+fn print_journey<W: Display>(tracker: &JourneyTracker<W>, item: usize) -> Result<(), UnknownItem> {
+    let journey = tracker.journey(item).ok_or(UnknownItem(item))?;
+    for step in journey {
         println!(
-            "Monkey {i} inspected items {} times.",
-            monkey.number_of_inspections
+            "Round {}: item {item} is at monkey {} with a worry level of {}.",
+            step.round, step.monkey, step.worry_level
         );
     }
+    println!("Item {item} changed hands {} times.", journey.len() - 1);
+    println!(
+        "Items change hands {:.3} times per round on average.",
+        tracker.average_hops_per_round()
+    );
+    Ok(())
+}
 
-    let (max, second_to_max) = monkeys
+/// Returns the number of inspections of the `top` most active monkeys, most
+/// active first.
+fn most_active(inspections: &[u64], top: usize) -> Vec<u64> {
+    let mut inspections = inspections.to_vec();
+    inspections.sort_unstable_by(|a, b| b.cmp(a));
+    inspections.truncate(top);
+    inspections
+}
+
+/// The level of monkey business is the product of the number of inspections of
+/// the most active monkeys. Returns `None` if it does not fit into a `u128`.
+fn monkey_business(most_active: &[u64]) -> Option<u128> {
+    most_active
         .iter()
-        .map(|monkey| monkey.number_of_inspections)
-        .fold((0, 0), |(max, second_to_max), x| {
-            if x > max {
-                (x, max)
-            } else if x > second_to_max {
-                (max, x)
-            } else {
-                (max, second_to_max)
-            }
+        .try_fold(1u128, |product, n| product.checked_mul(*n as u128))
+}
+
+/// Evaluates every monkey's operation on the same worry levels, once compiled
+/// and once interpreted, and checks that the results match.
+fn benchmark_operations(monkeys: &[Monkey]) {
+    const NUM_EVALUATIONS: u64 = 10_000_000;
+    for (i, monkey) in monkeys.iter().enumerate() {
+        let worry_levels = (0..NUM_EVALUATIONS).map(|n| n % 1000);
+
+        // operations may fail for some worry levels, so the results are
+        // summed up together with the number of errors
+        let checksum =
+            |(sum, errors): (u64, u64), result: Result<u64, ArithmeticError>| match result {
+                Ok(value) => (sum.wrapping_add(value), errors),
+                Err(_) => (sum, errors + 1),
+            };
+
+        let start = std::time::Instant::now();
+        let interpreted = worry_levels.clone().fold((0, 0), |sum, worry_level| {
+            checksum(
+                sum,
+                monkey
+                    .operation
+                    .interpret(&std::hint::black_box(worry_level)),
+            )
         });
-    println!("max: {max}\nsecond to max: {second_to_max}");
-    println!("result: {}", max * second_to_max);
+        let interpreter_time = start.elapsed();
+
+        let start = std::time::Instant::now();
+        let compiled = worry_levels.fold((0, 0), |sum, worry_level| {
+            checksum(
+                sum,
+                monkey
+                    .operation
+                    .evaluate(&std::hint::black_box(worry_level)),
+            )
+        });
+        let compiled_time = start.elapsed();
+
+        assert_eq!(interpreted, compiled);
+        println!(
+            "Monkey {i} ({}): interpreted {interpreter_time:?}, compiled {compiled_time:?}",
+            monkey.operation
+        );
+    }
+}
+
+/// Returns the command line argument that follows `flag`, if any.
+fn argument_after(flag: &str) -> Option<String> {
+    std::env::args()
+        .skip_while(|argument| argument != flag)
+        .nth(1)
+}
+
+/// Prints the error with its message rather than its `Debug` form, so that
+/// e.g. the line of a parse error reaches the user.
+fn main() {
+    if let Err(error) = solve() {
+        eprintln!("{error}");
+        std::process::exit(1);
+    }
+}
+
+fn solve() -> Result<(), Box<dyn Error>> {
+    // `--input <file>` reads the notes from another file
+    let path = argument_after("--input").unwrap_or_else(|| "real_input.txt".to_string());
+    let contents = std::fs::read_to_string(path)?;
+    let monkeys = notes::parse_input(&contents)?;
+    if std::env::args().any(|argument| argument == "--benchmark") {
+        benchmark_operations(&monkeys);
+        return Ok(());
+    }
+    // `--part1` changes the defaults to the 20 rounds of part 1, where worry
+    // levels are divided by three after every inspection
+    let (relief_rule, num_rounds) = if std::env::args().any(|argument| argument == "--part1") {
+        (ReliefRule::DivideBy(3), 20)
+    } else {
+        (ReliefRule::ModuloLcm, 10000)
+    };
+    // `--relief divide:N|lcm|none` and `--rounds <n>` override the defaults
+    let relief_rule = argument_after("--relief").map_or(Ok(relief_rule), |rule| rule.parse())?;
+    let num_rounds = argument_after("--rounds").map_or(Ok(num_rounds), |n| n.parse())?;
+    // `--top <n>` sets how many of the most active monkeys are multiplied
+    let top = argument_after("--top").map_or(Ok(2), |n| n.parse())?;
+    // `--worry-levels u64|u128|big` selects the number type for worry levels
+    let mode = argument_after("--worry-levels")
+        .map_or(Ok(WorryLevelMode::CheckedU64), |mode| mode.parse())?;
+
+    // `--trace` narrates every inspection like the puzzle's example walkthrough
+    let trace = std::env::args().any(|argument| argument == "--trace");
+    // `--journey <item>` follows the item with that id through the monkeys
+    let journey = argument_after("--journey")
+        .map(|item| item.parse())
+        .transpose()?;
+
+    // `--snapshot <file>` saves the state after the last round as notes that
+    // can be read back with `--input`
+    let snapshot = argument_after("--snapshot");
+
+    // `--cycles` computes the inspections for any number of rounds by
+    // detecting the cycle every item eventually runs through
+    let inspections = if std::env::args().any(|argument| argument == "--cycles") {
+        if relief_rule != ReliefRule::ModuloLcm {
+            return Err("--cycles requires the relief rule \"lcm\"".into());
+        }
+        let modulus = worry_modulus::<u64>(&monkeys)?;
+        cycles::count_inspections(&monkeys, modulus, num_rounds)?
+    } else {
+        match mode {
+            WorryLevelMode::CheckedU64 => run::<u64>(
+                monkeys,
+                relief_rule,
+                num_rounds,
+                trace,
+                journey,
+                snapshot.as_deref(),
+            )?,
+            WorryLevelMode::U128 => run::<u128>(
+                monkeys,
+                relief_rule,
+                num_rounds,
+                trace,
+                journey,
+                snapshot.as_deref(),
+            )?,
+            WorryLevelMode::BigInteger => run::<big_uint::BigUint>(
+                monkeys,
+                relief_rule,
+                num_rounds,
+                trace,
+                journey,
+                snapshot.as_deref(),
+            )?,
+        }
+    };
+    for (i, number_of_inspections) in inspections.iter().enumerate() {
+        println!("Monkey {i} inspected items {number_of_inspections} times.");
+    }
+
+    let most_active = most_active(&inspections, top);
+    let counts: Vec<_> = most_active.iter().map(|n| n.to_string()).collect();
+    println!("most active: {}", counts.join(", "));
+    let result = monkey_business(&most_active).ok_or(ArithmeticError::Overflow)?;
+    println!("result: {result}");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{big_uint::BigUint, notes, worry_modulus, ModulusError};
+
+    /// The example notes from the puzzle description.
+    pub(crate) const EXAMPLE: &str = include_str!("../input.txt");
+
+    #[test]
+    fn modulus_of_the_example() {
+        let monkeys = notes::parse_input(EXAMPLE).unwrap();
+        assert_eq!(worry_modulus::<u64>(&monkeys), Ok(23 * 19 * 13 * 17));
+    }
+
+    #[test]
+    fn modulus_requires_operations_that_only_add_and_multiply() {
+        for operation in ["old / 2 + 7", "old - 1", "old % 5", "(old + 1) * old / 3"] {
+            let notes = EXAMPLE.replace("new = old + 6", &format!("new = {operation}"));
+            let monkeys = notes::parse_input(&notes).unwrap();
+            assert_eq!(
+                worry_modulus::<u64>(&monkeys),
+                Err(ModulusError::IncompatibleOperation { monkey: 1 })
+            );
+        }
+        let notes = EXAMPLE.replace("new = old + 6", "new = (old + 6) * 2 * old + 1");
+        assert!(worry_modulus::<u64>(&notes::parse_input(&notes).unwrap()).is_ok());
+    }
+
+    #[test]
+    fn modulus_wider_than_u64() {
+        let notes = EXAMPLE
+            .replace("divisible by 23", "divisible by 4294967311")
+            .replace("divisible by 19", "divisible by 4294967357");
+        let monkeys = notes::parse_input(&notes).unwrap();
+        let modulus = 4294967311u128 * 4294967357 * 13 * 17;
+        assert_eq!(worry_modulus::<u64>(&monkeys), Err(ModulusError::Overflow));
+        assert_eq!(worry_modulus::<u128>(&monkeys), Ok(modulus));
+        assert_eq!(
+            worry_modulus::<BigUint>(&monkeys).map(|modulus| modulus.to_string()),
+            Ok(modulus.to_string())
+        );
+    }
 }
@@ -0,0 +1,629 @@
+use std::{
+    collections::{BTreeMap, HashSet},
+    error::Error,
+    fmt::Display,
+};
+
+use crate::{Item, Monkey, Operation, WorryLevel};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum ParseErrorKind {
+    ExpectedMonkeyHeader,
+    InvalidMonkeyId(String),
+    DuplicateMonkey(u64),
+    /// Monkey ids have to be `0..n` without gaps.
+    MissingMonkey(u64),
+    UnknownLine(String),
+    DuplicateField(&'static str),
+    MissingField(&'static str),
+    InvalidNumber(String),
+    InvalidOperation(String),
+    ExpectedDivisibilityTest,
+    ExpectedThrowTarget,
+    UnknownTargetMonkey(u64),
+    /// The number of item ids differs from the number of starting items.
+    ItemIdCount {
+        ids: usize,
+        items: usize,
+    },
+    DuplicateItemId(usize),
+}
+
+/// An error in the monkey notes. `line` is 1-based; for errors about a whole
+/// monkey (like a missing field) it is the line of the monkey's header.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct ParseError {
+    pub(crate) line: usize,
+    pub(crate) kind: ParseErrorKind,
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_fmt(format_args!("line {}: ", self.line))?;
+        match &self.kind {
+            ParseErrorKind::ExpectedMonkeyHeader => f.write_str("expected \"Monkey <id>:\""),
+            ParseErrorKind::InvalidMonkeyId(id) => {
+                f.write_fmt(format_args!("invalid monkey id \"{id}\""))
+            }
+            ParseErrorKind::DuplicateMonkey(id) => {
+                f.write_fmt(format_args!("monkey {id} is defined more than once"))
+            }
+            ParseErrorKind::MissingMonkey(id) => {
+                f.write_fmt(format_args!("monkey {id} is missing"))
+            }
+            ParseErrorKind::UnknownLine(line) => {
+                f.write_fmt(format_args!("unexpected line \"{line}\""))
+            }
+            ParseErrorKind::DuplicateField(label) => {
+                f.write_fmt(format_args!("\"{label}\" appears more than once"))
+            }
+            ParseErrorKind::MissingField(label) => {
+                f.write_fmt(format_args!("\"{label}\" is missing"))
+            }
+            ParseErrorKind::InvalidNumber(number) => {
+                f.write_fmt(format_args!("invalid number \"{number}\""))
+            }
+            ParseErrorKind::InvalidOperation(message) => {
+                f.write_fmt(format_args!("invalid operation ({message})"))
+            }
+            ParseErrorKind::ExpectedDivisibilityTest => {
+                f.write_str("expected \"Test: divisible by <number>\"")
+            }
+            ParseErrorKind::ExpectedThrowTarget => f.write_str("expected \"throw to monkey <id>\""),
+            ParseErrorKind::UnknownTargetMonkey(id) => {
+                f.write_fmt(format_args!("there is no monkey {id} to throw to"))
+            }
+            ParseErrorKind::ItemIdCount { ids, items } => f.write_fmt(format_args!(
+                "{ids} item ids are given for {items} starting items"
+            )),
+            ParseErrorKind::DuplicateItemId(id) => {
+                f.write_fmt(format_args!("item {id} is held more than once"))
+            }
+        }
+    }
+}
+
+impl Error for ParseError {}
+
+const STARTING_ITEMS: &str = "Starting items:";
+const OPERATION: &str = "Operation:";
+const TEST: &str = "Test:";
+const IF_TRUE: &str = "If true:";
+const IF_FALSE: &str = "If false:";
+// only written to snapshots, so that a simulation can be resumed
+const ITEM_IDS: &str = "Item ids:";
+const INSPECTIONS: &str = "Inspections:";
+
+fn parse_number(string: &str, line: usize) -> Result<u64, ParseError> {
+    string.trim().parse().map_err(|_| ParseError {
+        line,
+        kind: ParseErrorKind::InvalidNumber(string.trim().to_string()),
+    })
+}
+
+/// Sets a field that may only be given once per monkey.
+fn set_once<T>(
+    field: &mut Option<T>,
+    value: T,
+    label: &'static str,
+    line: usize,
+) -> Result<(), ParseError> {
+    if field.is_some() {
+        return Err(ParseError {
+            line,
+            kind: ParseErrorKind::DuplicateField(label),
+        });
+    }
+    *field = Some(value);
+    Ok(())
+}
+
+struct ParsedMonkey {
+    id: u64,
+    header_line: usize,
+    /// Lines of the "If true:" and "If false:" entries.
+    target_lines: [usize; 2],
+    /// The ids of the starting items and their line, if given.
+    item_ids: Option<(Vec<usize>, usize)>,
+    monkey: Monkey,
+}
+
+/// Parses the notes of a single monkey. `lines` are the non-blank lines of
+/// the block together with their 1-based line numbers.
+fn parse_monkey(lines: &[(usize, &str)]) -> Result<ParsedMonkey, ParseError> {
+    let (header_line, header) = lines[0];
+    let id = header
+        .strip_prefix("Monkey")
+        .and_then(|rest| rest.trim().strip_suffix(':'))
+        .ok_or(ParseError {
+            line: header_line,
+            kind: ParseErrorKind::ExpectedMonkeyHeader,
+        })?
+        .trim();
+    let id = id.parse::<u64>().map_err(|_| ParseError {
+        line: header_line,
+        kind: ParseErrorKind::InvalidMonkeyId(id.to_string()),
+    })?;
+
+    let mut items = None;
+    let mut operation = None;
+    let mut test_divisor = None;
+    let mut target_monkey_if_true = None;
+    let mut target_monkey_if_false = None;
+    let mut item_ids = None;
+    let mut number_of_inspections = None;
+    let throw_target = |rest: &str, line: usize| {
+        let target = rest
+            .trim()
+            .strip_prefix("throw to monkey")
+            .ok_or(ParseError {
+                line,
+                kind: ParseErrorKind::ExpectedThrowTarget,
+            })?;
+        parse_number(target, line)
+    };
+    for (line, content) in lines.iter().skip(1).copied() {
+        if let Some(rest) = content.strip_prefix(STARTING_ITEMS) {
+            // the items are numbered once all monkeys are parsed
+            let starting_items = rest
+                .split(',')
+                .map(str::trim)
+                .filter(|item| !item.is_empty())
+                .map(|item| parse_number(item, line).map(|worry_level| Item { id: 0, worry_level }))
+                .collect::<Result<Vec<_>, _>>()?;
+            set_once(&mut items, starting_items, STARTING_ITEMS, line)?;
+        } else if let Some(rest) = content.strip_prefix(OPERATION) {
+            let parsed = Operation::from_string(rest.trim()).map_err(|error| ParseError {
+                line,
+                kind: ParseErrorKind::InvalidOperation(error.to_string()),
+            })?;
+            set_once(&mut operation, parsed, OPERATION, line)?;
+        } else if let Some(rest) = content.strip_prefix(TEST) {
+            let divisor = rest.trim().strip_prefix("divisible by").ok_or(ParseError {
+                line,
+                kind: ParseErrorKind::ExpectedDivisibilityTest,
+            })?;
+            set_once(&mut test_divisor, parse_number(divisor, line)?, TEST, line)?;
+        } else if let Some(rest) = content.strip_prefix(IF_TRUE) {
+            set_once(
+                &mut target_monkey_if_true,
+                (throw_target(rest, line)?, line),
+                IF_TRUE,
+                line,
+            )?;
+        } else if let Some(rest) = content.strip_prefix(IF_FALSE) {
+            set_once(
+                &mut target_monkey_if_false,
+                (throw_target(rest, line)?, line),
+                IF_FALSE,
+                line,
+            )?;
+        } else if let Some(rest) = content.strip_prefix(ITEM_IDS) {
+            let ids = rest
+                .split(',')
+                .map(str::trim)
+                .filter(|id| !id.is_empty())
+                .map(|id| parse_number(id, line).map(|id| id as usize))
+                .collect::<Result<Vec<_>, _>>()?;
+            set_once(&mut item_ids, (ids, line), ITEM_IDS, line)?;
+        } else if let Some(rest) = content.strip_prefix(INSPECTIONS) {
+            set_once(
+                &mut number_of_inspections,
+                parse_number(rest, line)?,
+                INSPECTIONS,
+                line,
+            )?;
+        } else {
+            return Err(ParseError {
+                line,
+                kind: ParseErrorKind::UnknownLine(content.to_string()),
+            });
+        }
+    }
+
+    let missing = |label| ParseError {
+        line: header_line,
+        kind: ParseErrorKind::MissingField(label),
+    };
+    let (target_monkey_if_true, if_true_line) =
+        target_monkey_if_true.ok_or_else(|| missing(IF_TRUE))?;
+    let (target_monkey_if_false, if_false_line) =
+        target_monkey_if_false.ok_or_else(|| missing(IF_FALSE))?;
+    let items = items.ok_or_else(|| missing(STARTING_ITEMS))?;
+    if let Some((ids, line)) = &item_ids {
+        if ids.len() != items.len() {
+            return Err(ParseError {
+                line: *line,
+                kind: ParseErrorKind::ItemIdCount {
+                    ids: ids.len(),
+                    items: items.len(),
+                },
+            });
+        }
+    }
+    Ok(ParsedMonkey {
+        id,
+        header_line,
+        target_lines: [if_true_line, if_false_line],
+        item_ids,
+        monkey: Monkey {
+            items,
+            operation: operation.ok_or_else(|| missing(OPERATION))?,
+            test_divisor: test_divisor.ok_or_else(|| missing(TEST))?,
+            target_monkey_if_true,
+            target_monkey_if_false,
+            number_of_inspections: number_of_inspections.unwrap_or(0),
+        },
+    })
+}
+
+/// Parses the monkey notes. Monkeys are separated by blank lines and may
+/// appear in any order; the lines within a monkey's notes are recognized by
+/// their labels, so their order and indentation do not matter either.
+///
+/// Snapshots also give the ids of the items and the number of inspections so
+/// far. Item ids have to be given for all monkeys or none; without them the
+/// items are numbered in order.
+pub(crate) fn parse_input(string: &str) -> Result<Vec<Monkey>, ParseError> {
+    let mut blocks: Vec<Vec<(usize, &str)>> = Vec::new();
+    let mut previous_line_was_blank = true;
+    for (index, line) in string.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            previous_line_was_blank = true;
+            continue;
+        }
+        if previous_line_was_blank {
+            blocks.push(Vec::new());
+        }
+        blocks.last_mut().unwrap().push((index + 1, line));
+        previous_line_was_blank = false;
+    }
+
+    let mut monkeys = BTreeMap::new();
+    for block in &blocks {
+        let parsed = parse_monkey(block)?;
+        if monkeys.contains_key(&parsed.id) {
+            return Err(ParseError {
+                line: parsed.header_line,
+                kind: ParseErrorKind::DuplicateMonkey(parsed.id),
+            });
+        }
+        monkeys.insert(parsed.id, parsed);
+    }
+
+    for (expected_id, parsed) in monkeys.values().enumerate() {
+        if parsed.id != expected_id as u64 {
+            return Err(ParseError {
+                line: parsed.header_line,
+                kind: ParseErrorKind::MissingMonkey(expected_id as u64),
+            });
+        }
+    }
+    let number_of_monkeys = monkeys.len() as u64;
+    for parsed in monkeys.values() {
+        let targets = [
+            parsed.monkey.target_monkey_if_true,
+            parsed.monkey.target_monkey_if_false,
+        ];
+        for (target, line) in targets.into_iter().zip(parsed.target_lines) {
+            if target >= number_of_monkeys {
+                return Err(ParseError {
+                    line,
+                    kind: ParseErrorKind::UnknownTargetMonkey(target),
+                });
+            }
+        }
+    }
+    if monkeys.values().any(|parsed| parsed.item_ids.is_some()) {
+        let mut seen = HashSet::new();
+        for parsed in monkeys.values_mut() {
+            let (ids, line) = parsed.item_ids.take().ok_or(ParseError {
+                line: parsed.header_line,
+                kind: ParseErrorKind::MissingField(ITEM_IDS),
+            })?;
+            for (item, id) in parsed.monkey.items.iter_mut().zip(ids) {
+                if !seen.insert(id) {
+                    return Err(ParseError {
+                        line,
+                        kind: ParseErrorKind::DuplicateItemId(id),
+                    });
+                }
+                item.id = id;
+            }
+        }
+        return Ok(monkeys.into_values().map(|parsed| parsed.monkey).collect());
+    }
+    let mut monkeys: Vec<_> = monkeys.into_values().map(|parsed| parsed.monkey).collect();
+    for (id, item) in monkeys
+        .iter_mut()
+        .flat_map(|monkey| monkey.items.iter_mut())
+        .enumerate()
+    {
+        item.id = id;
+    }
+    Ok(monkeys)
+}
+
+/// A worry level that is too large to be read back from the notes.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct UnreadableWorryLevel {
+    monkey: usize,
+    worry_level: String,
+}
+
+impl Display for UnreadableWorryLevel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_fmt(format_args!(
+            "monkey {} holds an item with the worry level {}, which does not fit into the notes",
+            self.monkey, self.worry_level
+        ))
+    }
+}
+
+impl Error for UnreadableWorryLevel {}
+
+/// Writes the notes of all monkeys in the format of the puzzle input, with the
+/// items they currently hold as their starting items. Parsing the result gives
+/// back the same monkeys, so the state of a simulation can be saved and
+/// resumed: once the items are no longer numbered in order, their ids follow
+/// the starting items, and once a monkey has inspected an item, the number of
+/// inspections follows the test. Notes that were just parsed are written
+/// exactly like the puzzle input. Worry levels are read as `u64`, so larger
+/// ones are refused.
+pub(crate) fn serialize<W: WorryLevel>(
+    monkeys: &[Monkey<W>],
+) -> Result<String, UnreadableWorryLevel> {
+    let items_in_order = monkeys
+        .iter()
+        .flat_map(|monkey| &monkey.items)
+        .enumerate()
+        .all(|(id, item)| item.id == id);
+    let any_inspections = monkeys
+        .iter()
+        .any(|monkey| monkey.number_of_inspections != 0);
+    let mut notes = Vec::new();
+    for (i, monkey) in monkeys.iter().enumerate() {
+        if let Some(item) = monkey
+            .items
+            .iter()
+            .find(|item| item.worry_level.to_u64().is_none())
+        {
+            return Err(UnreadableWorryLevel {
+                monkey: i,
+                worry_level: item.worry_level.to_string(),
+            });
+        }
+        let mut note = format!("Monkey {i}:\n{monkey}");
+        if !items_in_order {
+            let ids: Vec<_> = monkey
+                .items
+                .iter()
+                .map(|item| format!(" {}", item.id))
+                .collect();
+            note.push_str(&format!("  {ITEM_IDS}{}\n", ids.join(",")));
+        }
+        if any_inspections {
+            note.push_str(&format!(
+                "  {INSPECTIONS} {}\n",
+                monkey.number_of_inspections
+            ));
+        }
+        notes.push(note);
+    }
+    Ok(notes.join("\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_input, serialize, ParseError, ParseErrorKind};
+    use crate::{
+        big_uint::BigUint, inspection_counts, relief::Relief, simulate, tests::EXAMPLE,
+        trace::NoTrace,
+    };
+
+    /// Parses the notes and prints them in the canonical format.
+    fn canonical(notes: &str) -> String {
+        serialize(&parse_input(notes).unwrap()).unwrap()
+    }
+
+    fn error(notes: &str) -> ParseError {
+        parse_input(notes).err().unwrap()
+    }
+
+    #[test]
+    fn fresh_notes_are_written_like_the_input() {
+        assert_eq!(canonical(EXAMPLE), EXAMPLE);
+        let real_input = include_str!("../real_input.txt");
+        assert_eq!(canonical(real_input), real_input);
+    }
+
+    #[test]
+    fn line_breaks_and_whitespace() {
+        let expected = canonical(EXAMPLE);
+        assert_eq!(canonical(&EXAMPLE.replace('\n', "\r\n")), expected);
+        assert_eq!(canonical(EXAMPLE.trim_end()), expected);
+        assert_eq!(canonical(&format!("\n\n{EXAMPLE}\n\n\n")), expected);
+        let spaced: String = EXAMPLE
+            .lines()
+            .map(|line| format!("\t {line}   \n"))
+            .collect();
+        assert_eq!(canonical(&spaced), expected);
+        assert_eq!(canonical(&EXAMPLE.replace("\n\n", "\n \n\t\n")), expected);
+    }
+
+    #[test]
+    fn monkeys_and_fields_in_any_order() {
+        let expected = canonical(EXAMPLE);
+        let mut blocks: Vec<_> = EXAMPLE.trim_end().split("\n\n").collect();
+        blocks.reverse();
+        assert_eq!(canonical(&blocks.join("\n\n")), expected);
+        let shuffled: Vec<_> = blocks
+            .iter()
+            .map(|block| {
+                let mut lines: Vec<_> = block.lines().collect();
+                lines[1..].reverse();
+                lines.join("\n")
+            })
+            .collect();
+        assert_eq!(canonical(&shuffled.join("\n\n")), expected);
+    }
+
+    #[test]
+    fn duplicate_and_missing_monkeys() {
+        // the second "Monkey 1:" header is on line 22
+        let duplicate = EXAMPLE.replace("Monkey 3:", "Monkey 1:");
+        assert_eq!(
+            error(&duplicate),
+            ParseError {
+                line: 22,
+                kind: ParseErrorKind::DuplicateMonkey(1),
+            }
+        );
+        // without monkey 2, monkey 3 (on line 22) is where it goes wrong
+        let missing = EXAMPLE
+            .replace("monkey 1", "monkey 0")
+            .replace("monkey 2", "monkey 0")
+            .replace("Monkey 2:", "Monkey 4:");
+        assert_eq!(
+            error(&missing),
+            ParseError {
+                line: 22,
+                kind: ParseErrorKind::MissingMonkey(2),
+            }
+        );
+    }
+
+    #[test]
+    fn unknown_targets() {
+        let notes = EXAMPLE.replace("If false: throw to monkey 0", "If false: throw to monkey 7");
+        assert_eq!(
+            error(&notes),
+            ParseError {
+                line: 13,
+                kind: ParseErrorKind::UnknownTargetMonkey(7),
+            }
+        );
+        let notes = EXAMPLE.replace("If true: throw to monkey 2", "If true: throw to monkey 4");
+        assert_eq!(
+            error(&notes),
+            ParseError {
+                line: 5,
+                kind: ParseErrorKind::UnknownTargetMonkey(4),
+            }
+        );
+    }
+
+    #[test]
+    fn field_errors() {
+        let notes = EXAMPLE.replacen("  Test: divisible by 19\n", "", 1);
+        assert_eq!(
+            error(&notes),
+            ParseError {
+                line: 8,
+                kind: ParseErrorKind::MissingField("Test:"),
+            }
+        );
+        let notes = EXAMPLE.replacen(
+            "  Operation: new = old + 6\n",
+            "  Operation: new = old + 6\n  Operation: new = old\n",
+            1,
+        );
+        assert_eq!(
+            error(&notes),
+            ParseError {
+                line: 11,
+                kind: ParseErrorKind::DuplicateField("Operation:"),
+            }
+        );
+        let notes = EXAMPLE.replace("79, 60, 97", "79, sixty, 97");
+        assert_eq!(
+            error(&notes),
+            ParseError {
+                line: 16,
+                kind: ParseErrorKind::InvalidNumber("sixty".to_string()),
+            }
+        );
+        let notes = EXAMPLE.replace("new = old + 3", "new = old +");
+        assert_eq!(error(&notes).line, 24);
+        assert!(matches!(
+            error(&notes).kind,
+            ParseErrorKind::InvalidOperation(_)
+        ));
+        let notes = EXAMPLE.replace("Monkey 0:", "Monkey zero:");
+        assert_eq!(
+            error(&notes),
+            ParseError {
+                line: 1,
+                kind: ParseErrorKind::InvalidMonkeyId("zero".to_string()),
+            }
+        );
+    }
+
+    #[test]
+    fn snapshots_resume_the_simulation() {
+        for relief in [Relief::DivideBy(3), Relief::Modulo(23 * 19 * 13 * 17)] {
+            for num_rounds in [0, 1, 7, 20] {
+                let monkeys = parse_input(EXAMPLE).unwrap();
+                let monkeys = simulate::<u64>(monkeys, &relief, num_rounds, &mut NoTrace).unwrap();
+                let notes = serialize(&monkeys).unwrap();
+                let parsed = parse_input(&notes).unwrap();
+                assert_eq!(serialize(&parsed).unwrap(), notes);
+                for (parsed, monkey) in parsed.iter().zip(&monkeys) {
+                    assert_eq!(parsed.items, monkey.items);
+                    assert_eq!(parsed.number_of_inspections, monkey.number_of_inspections);
+                }
+                let resumed = simulate::<u64>(parsed, &relief, 5, &mut NoTrace).unwrap();
+                let uninterrupted = simulate::<u64>(
+                    parse_input(EXAMPLE).unwrap(),
+                    &relief,
+                    num_rounds + 5,
+                    &mut NoTrace,
+                )
+                .unwrap();
+                assert_eq!(
+                    inspection_counts(&resumed),
+                    inspection_counts(&uninterrupted)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn snapshots_refuse_worry_levels_beyond_u64() {
+        let monkeys = parse_input(EXAMPLE).unwrap();
+        let monkeys = simulate::<BigUint>(monkeys, &Relief::None, 20, &mut NoTrace).unwrap();
+        assert!(serialize(&monkeys).is_err());
+    }
+
+    #[test]
+    fn item_id_errors() {
+        let notes = EXAMPLE
+            .replace("79, 98\n", "79, 98\n  Item ids: 0, 1\n")
+            .replace("75, 74\n", "75, 74\n  Item ids: 2, 3, 4, 5\n")
+            .replace("60, 97\n", "60, 97\n  Item ids: 6, 7, 8\n")
+            .replace("items: 74\n", "items: 74\n  Item ids: 9\n");
+        assert_eq!(canonical(&notes), EXAMPLE);
+        assert_eq!(
+            error(&notes.replacen("  Item ids: 0, 1\n", "", 1)),
+            ParseError {
+                line: 1,
+                kind: ParseErrorKind::MissingField("Item ids:"),
+            }
+        );
+        assert_eq!(
+            error(&notes.replacen("Item ids: 0, 1", "Item ids: 0", 1)),
+            ParseError {
+                line: 3,
+                kind: ParseErrorKind::ItemIdCount { ids: 1, items: 2 },
+            }
+        );
+        assert_eq!(
+            error(&notes.replacen("Item ids: 0, 1", "Item ids: 0, 2", 1)),
+            ParseError {
+                line: 11,
+                kind: ParseErrorKind::DuplicateItemId(2),
+            }
+        );
+    }
+}
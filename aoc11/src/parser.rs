@@ -0,0 +1,518 @@
+use std::{error::Error, fmt::Display};
+
+use crate::{
+    lexer::{Lexeme, Token},
+    worry_level::{ArithmeticError, WorryLevel},
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Operator {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Remainder,
+}
+
+impl Operator {
+    fn from_token(token: Token) -> Option<Self> {
+        match token {
+            Token::Plus => Some(Operator::Add),
+            Token::Minus => Some(Operator::Subtract),
+            Token::Asterisk => Some(Operator::Multiply),
+            Token::Slash => Some(Operator::Divide),
+            Token::Percent => Some(Operator::Remainder),
+            _ => None,
+        }
+    }
+
+    fn precedence(&self) -> u8 {
+        match self {
+            Operator::Add | Operator::Subtract => 1,
+            Operator::Multiply | Operator::Divide | Operator::Remainder => 2,
+        }
+    }
+
+    pub fn apply<W: WorryLevel>(&self, lhs: &W, rhs: &W) -> Result<W, ArithmeticError> {
+        match self {
+            Operator::Add => lhs.checked_add(rhs),
+            Operator::Subtract => lhs.checked_sub(rhs),
+            Operator::Multiply => lhs.checked_mul(rhs),
+            Operator::Divide => lhs.checked_div(rhs),
+            Operator::Remainder => lhs.checked_rem(rhs),
+        }
+    }
+}
+
+impl Display for Operator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Operator::Add => "+",
+            Operator::Subtract => "-",
+            Operator::Multiply => "*",
+            Operator::Divide => "/",
+            Operator::Remainder => "%",
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum Expression {
+    Integer(u64),
+    Old,
+    Binary {
+        operator: Operator,
+        lhs: Box<Expression>,
+        rhs: Box<Expression>,
+    },
+}
+
+impl Expression {
+    pub fn evaluate<W: WorryLevel>(&self, old_value: &W) -> Result<W, ArithmeticError> {
+        match self {
+            Expression::Integer(value) => Ok(W::from_u64(*value)),
+            Expression::Old => Ok(old_value.clone()),
+            Expression::Binary { operator, lhs, rhs } => {
+                operator.apply(&lhs.evaluate(old_value)?, &rhs.evaluate(old_value)?)
+            }
+        }
+    }
+
+    /// Whether the expression only adds and multiplies. Only then does its
+    /// result modulo some number depend on nothing but `old` modulo that number.
+    pub fn only_adds_and_multiplies(&self) -> bool {
+        match self {
+            Expression::Integer(_) | Expression::Old => true,
+            Expression::Binary { operator, lhs, rhs } => {
+                matches!(operator, Operator::Add | Operator::Multiply)
+                    && lhs.only_adds_and_multiplies()
+                    && rhs.only_adds_and_multiplies()
+            }
+        }
+    }
+
+    fn precedence(&self) -> u8 {
+        match self {
+            Expression::Integer(_) | Expression::Old => u8::MAX,
+            Expression::Binary { operator, .. } => operator.precedence(),
+        }
+    }
+}
+
+impl Display for Expression {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Expression::Integer(value) => f.write_fmt(format_args!("{value}")),
+            Expression::Old => f.write_str("old"),
+            Expression::Binary { operator, lhs, rhs } => {
+                // all operators are left-associative, so a right operand of the
+                // same precedence needs parentheses as well
+                if lhs.precedence() < operator.precedence() {
+                    f.write_fmt(format_args!("({lhs})"))?;
+                } else {
+                    f.write_fmt(format_args!("{lhs}"))?;
+                }
+                f.write_fmt(format_args!(" {operator} "))?;
+                if rhs.precedence() <= operator.precedence() {
+                    f.write_fmt(format_args!("({rhs})"))
+                } else {
+                    f.write_fmt(format_args!("{rhs}"))
+                }
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum ParserErrorKind {
+    UnexpectedToken(Token),
+    ExpectedToken {
+        expected: Token,
+        found: Token,
+    },
+    /// The parentheses or the expression tree are nested deeper than
+    /// `MAX_NESTING_DEPTH`.
+    TooDeeplyNested,
+}
+
+/// An error in an operation. `column` is 1-based and points at the token that
+/// could not be parsed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct ParserError {
+    pub(crate) column: usize,
+    pub(crate) kind: ParserErrorKind,
+}
+
+impl Display for ParserError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_fmt(format_args!("column {}: ", self.column))?;
+        match self.kind {
+            ParserErrorKind::UnexpectedToken(token) => {
+                f.write_fmt(format_args!("unexpected \"{token}\""))
+            }
+            ParserErrorKind::ExpectedToken { expected, found } => {
+                f.write_fmt(format_args!("expected \"{expected}\", found \"{found}\""))
+            }
+            ParserErrorKind::TooDeeplyNested => f.write_fmt(format_args!(
+                "the operation is nested deeper than {MAX_NESTING_DEPTH} levels"
+            )),
+        }
+    }
+}
+
+impl Error for ParserError {}
+
+/// How deeply parentheses and the expression tree may be nested. Parsing,
+/// evaluating, printing and compiling an expression all recurse over the tree,
+/// so without a limit a long enough operation overflows the stack.
+const MAX_NESTING_DEPTH: usize = 256;
+
+pub(crate) struct Parser {
+    pub(crate) lexemes: Vec<Lexeme>,
+    pub(crate) index: usize,
+    /// The number of currently open parentheses.
+    pub(crate) open_parentheses: usize,
+}
+
+impl Parser {
+    pub fn new(lexemes: Vec<Lexeme>) -> Self {
+        Self {
+            lexemes,
+            index: 0,
+            open_parentheses: 0,
+        }
+    }
+
+    pub fn current(&self) -> Token {
+        self.lexemes[self.index].token
+    }
+
+    fn error(&self, kind: ParserErrorKind) -> ParserError {
+        ParserError {
+            column: self.lexemes[self.index].column,
+            kind,
+        }
+    }
+
+    pub fn advance(&mut self) {
+        if self.index + 1 < self.lexemes.len() {
+            self.index += 1
+        }
+    }
+
+    fn expect(&mut self, token: Token) -> Result<(), ParserError> {
+        if self.current() != token {
+            return Err(self.error(ParserErrorKind::ExpectedToken {
+                expected: token,
+                found: self.current(),
+            }));
+        }
+        self.advance();
+        Ok(())
+    }
+
+    /// Combines two operands, which are nested `depth` levels deep, into a
+    /// binary expression, unless that would exceed `MAX_NESTING_DEPTH`.
+    /// `column` is that of the operator.
+    fn binary(
+        operator: Operator,
+        (lhs, lhs_depth): (Expression, usize),
+        (rhs, rhs_depth): (Expression, usize),
+        column: usize,
+    ) -> Result<(Expression, usize), ParserError> {
+        let depth = lhs_depth.max(rhs_depth) + 1;
+        if depth > MAX_NESTING_DEPTH {
+            return Err(ParserError {
+                column,
+                kind: ParserErrorKind::TooDeeplyNested,
+            });
+        }
+        let expression = Expression::Binary {
+            operator,
+            lhs: Box::new(lhs),
+            rhs: Box::new(rhs),
+        };
+        Ok((expression, depth))
+    }
+
+    /// operation := "new" "=" expression END_OF_INPUT
+    pub fn operation(&mut self) -> Result<Expression, ParserError> {
+        self.expect(Token::New)?;
+        self.expect(Token::Equals)?;
+        let (expression, _) = self.expression()?;
+        if self.current() != Token::EndOfInput {
+            return Err(self.error(ParserErrorKind::UnexpectedToken(self.current())));
+        }
+        Ok(expression)
+    }
+
+    /// expression := term (("+" | "-") term)*
+    ///
+    /// Like `term` and `factor`, returns the expression together with the
+    /// depth of its tree.
+    pub fn expression(&mut self) -> Result<(Expression, usize), ParserError> {
+        let mut lhs = self.term()?;
+        while let Some(operator @ (Operator::Add | Operator::Subtract)) =
+            Operator::from_token(self.current())
+        {
+            let column = self.lexemes[self.index].column;
+            self.advance();
+            let rhs = self.term()?;
+            lhs = Self::binary(operator, lhs, rhs, column)?;
+        }
+        Ok(lhs)
+    }
+
+    /// term := factor (("*" | "/" | "%") factor)*
+    fn term(&mut self) -> Result<(Expression, usize), ParserError> {
+        let mut lhs = self.factor()?;
+        while let Some(operator @ (Operator::Multiply | Operator::Divide | Operator::Remainder)) =
+            Operator::from_token(self.current())
+        {
+            let column = self.lexemes[self.index].column;
+            self.advance();
+            let rhs = self.factor()?;
+            lhs = Self::binary(operator, lhs, rhs, column)?;
+        }
+        Ok(lhs)
+    }
+
+    /// factor := INTEGER | "old" | "(" expression ")"
+    fn factor(&mut self) -> Result<(Expression, usize), ParserError> {
+        match self.current() {
+            Token::Integer(value) => {
+                self.advance();
+                Ok((Expression::Integer(value), 0))
+            }
+            Token::Old => {
+                self.advance();
+                Ok((Expression::Old, 0))
+            }
+            Token::LeftParenthesis => {
+                if self.open_parentheses == MAX_NESTING_DEPTH {
+                    return Err(self.error(ParserErrorKind::TooDeeplyNested));
+                }
+                self.open_parentheses += 1;
+                self.advance();
+                let expression = self.expression()?;
+                self.expect(Token::RightParenthesis)?;
+                self.open_parentheses -= 1;
+                Ok(expression)
+            }
+            token => Err(self.error(ParserErrorKind::UnexpectedToken(token))),
+        }
+    }
+}
+
+pub(crate) fn parse_operation(lexemes: Vec<Lexeme>) -> Result<Expression, ParserError> {
+    Parser::new(lexemes).operation()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        parse_operation,
+        Expression::{self, Integer, Old},
+        Operator, ParserError, ParserErrorKind, MAX_NESTING_DEPTH,
+    };
+    use crate::lexer::{tokenize, LexerError, LexerErrorKind, Token};
+
+    fn parse(string: &str) -> Result<Expression, ParserError> {
+        parse_operation(tokenize(string).unwrap())
+    }
+
+    fn binary(operator: Operator, lhs: Expression, rhs: Expression) -> Expression {
+        Expression::Binary {
+            operator,
+            lhs: Box::new(lhs),
+            rhs: Box::new(rhs),
+        }
+    }
+
+    #[test]
+    fn precedence() {
+        assert_eq!(
+            parse("new = old + 2 * 3"),
+            Ok(binary(
+                Operator::Add,
+                Old,
+                binary(Operator::Multiply, Integer(2), Integer(3))
+            ))
+        );
+        assert_eq!(
+            parse("new = old * 2 + 3 % 4"),
+            Ok(binary(
+                Operator::Add,
+                binary(Operator::Multiply, Old, Integer(2)),
+                binary(Operator::Remainder, Integer(3), Integer(4))
+            ))
+        );
+        assert_eq!(
+            parse("new = old - 1 - 2"),
+            Ok(binary(
+                Operator::Subtract,
+                binary(Operator::Subtract, Old, Integer(1)),
+                Integer(2)
+            ))
+        );
+        assert_eq!(
+            parse("new = old / 2 / 3"),
+            Ok(binary(
+                Operator::Divide,
+                binary(Operator::Divide, Old, Integer(2)),
+                Integer(3)
+            ))
+        );
+    }
+
+    #[test]
+    fn parentheses() {
+        assert_eq!(
+            parse("new = (old + 2) * 3"),
+            Ok(binary(
+                Operator::Multiply,
+                binary(Operator::Add, Old, Integer(2)),
+                Integer(3)
+            ))
+        );
+        assert_eq!(
+            parse("new = old - (1 - 2)"),
+            Ok(binary(
+                Operator::Subtract,
+                Old,
+                binary(Operator::Subtract, Integer(1), Integer(2))
+            ))
+        );
+        assert_eq!(parse("new = ((old))"), Ok(Old));
+    }
+
+    #[test]
+    fn repeated_old() {
+        let expression = parse("new = old * old + old").unwrap();
+        assert_eq!(
+            expression,
+            binary(Operator::Add, binary(Operator::Multiply, Old, Old), Old)
+        );
+        assert_eq!(expression.evaluate(&3u64), Ok(12));
+    }
+
+    /// Printing an expression adds exactly the parentheses needed to parse it
+    /// back into the same tree.
+    #[test]
+    fn display_round_trip() {
+        for (input, printed) in [
+            ("new = old * 19", "old * 19"),
+            ("new = (old + 2) * 3", "(old + 2) * 3"),
+            ("new = old - (1 - 2)", "old - (1 - 2)"),
+            ("new = (old - 1) - 2", "old - 1 - 2"),
+            ("new = old / (2 * 3)", "old / (2 * 3)"),
+            ("new = ((old))*old", "old * old"),
+        ] {
+            let expression = parse(input).unwrap();
+            assert_eq!(expression.to_string(), printed);
+            assert_eq!(parse(&format!("new = {printed}")), Ok(expression));
+        }
+    }
+
+    #[test]
+    fn parser_errors() {
+        let error = |column, kind| Err(ParserError { column, kind });
+        assert_eq!(
+            parse("new = 3old"),
+            error(8, ParserErrorKind::UnexpectedToken(Token::Old))
+        );
+        assert_eq!(
+            parse("new = old +"),
+            error(12, ParserErrorKind::UnexpectedToken(Token::EndOfInput))
+        );
+        assert_eq!(
+            parse("new = (old"),
+            error(
+                11,
+                ParserErrorKind::ExpectedToken {
+                    expected: Token::RightParenthesis,
+                    found: Token::EndOfInput
+                }
+            )
+        );
+        assert_eq!(
+            parse("new = old )"),
+            error(
+                11,
+                ParserErrorKind::UnexpectedToken(Token::RightParenthesis)
+            )
+        );
+        assert_eq!(
+            parse("old = old"),
+            error(
+                1,
+                ParserErrorKind::ExpectedToken {
+                    expected: Token::New,
+                    found: Token::Old
+                }
+            )
+        );
+        assert_eq!(
+            parse("new = 3old").unwrap_err().to_string(),
+            "column 8: unexpected \"old\""
+        );
+        assert_eq!(
+            parse("new = (old").unwrap_err().to_string(),
+            "column 11: expected \")\", found \"end of input\""
+        );
+    }
+
+    #[test]
+    fn nesting_depth() {
+        let nested = |depth| format!("new = {}old{}", "(".repeat(depth), ")".repeat(depth));
+        assert_eq!(parse(&nested(MAX_NESTING_DEPTH)), Ok(Old));
+        assert_eq!(
+            parse(&nested(200_000)),
+            Err(ParserError {
+                column: 7 + MAX_NESTING_DEPTH,
+                kind: ParserErrorKind::TooDeeplyNested,
+            })
+        );
+        let chain = |length| format!("new = old{}", " + 1".repeat(length));
+        assert!(parse(&chain(MAX_NESTING_DEPTH)).is_ok());
+        // the operator that would exceed the limit is reported
+        assert_eq!(
+            parse(&chain(200_000)),
+            Err(ParserError {
+                column: 11 + 4 * MAX_NESTING_DEPTH,
+                kind: ParserErrorKind::TooDeeplyNested,
+            })
+        );
+        let right_nested = format!("new = {}old{}", "1 * (".repeat(200), ")".repeat(200));
+        assert!(parse(&right_nested).is_ok());
+        let right_nested = format!("new = {}old{}", "1 * (".repeat(300), ")".repeat(300));
+        assert_eq!(
+            parse(&right_nested).map_err(|error| error.kind),
+            Err(ParserErrorKind::TooDeeplyNested)
+        );
+    }
+
+    #[test]
+    fn lexer_errors() {
+        let error = |column, kind| Err(LexerError { column, kind });
+        assert_eq!(
+            tokenize("new = old $ 2"),
+            error(11, LexerErrorKind::UnexpectedCharacter('$'))
+        );
+        assert_eq!(
+            tokenize("new = foo"),
+            error(7, LexerErrorKind::UnknownIdentifier("foo".to_string()))
+        );
+        assert_eq!(
+            tokenize("new = 18446744073709551616"),
+            error(
+                7,
+                LexerErrorKind::IntegerOutOfRange("18446744073709551616".to_string())
+            )
+        );
+        assert_eq!(
+            tokenize("new = old $ 2").unwrap_err().to_string(),
+            "column 11: unexpected character '$'"
+        );
+    }
+}
@@ -0,0 +1,80 @@
+use std::{error::Error, fmt::Display, str::FromStr};
+
+use crate::{
+    worry_level::{ArithmeticError, WorryLevel},
+    worry_modulus, ModulusError, Monkey,
+};
+
+/// How worry levels are kept in check after every inspection, as given on the
+/// command line.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum ReliefRule {
+    /// `divide:N`: the worry level is divided by `N` (part 1 uses 3).
+    DivideBy(u64),
+    /// `lcm`: the worry level is reduced modulo the least common multiple of
+    /// all test divisors.
+    ModuloLcm,
+    /// `none`: the worry level is left as it is.
+    None,
+}
+
+impl ReliefRule {
+    /// Turns the rule into the relief applied during a simulation with worry
+    /// levels stored as `W`. The least common multiple of the test divisors is
+    /// only computed for `lcm`, and in `W`, so that it may exceed `u64`.
+    pub fn resolve<W: WorryLevel>(self, monkeys: &[Monkey]) -> Result<Relief<W>, ModulusError> {
+        Ok(match self {
+            ReliefRule::DivideBy(divisor) => Relief::DivideBy(divisor),
+            ReliefRule::ModuloLcm => Relief::Modulo(worry_modulus(monkeys)?),
+            ReliefRule::None => Relief::None,
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct UnknownReliefRule(String);
+
+impl Display for UnknownReliefRule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_fmt(format_args!(
+            "unknown relief rule \"{}\" (expected \"divide:N\" with N > 0, \"lcm\" or \"none\")",
+            self.0
+        ))
+    }
+}
+
+impl Error for UnknownReliefRule {}
+
+impl FromStr for ReliefRule {
+    type Err = UnknownReliefRule;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "lcm" => Ok(ReliefRule::ModuloLcm),
+            "none" => Ok(ReliefRule::None),
+            _ => s
+                .strip_prefix("divide:")
+                .and_then(|divisor| divisor.parse().ok())
+                .filter(|divisor| *divisor != 0)
+                .map(ReliefRule::DivideBy)
+                .ok_or_else(|| UnknownReliefRule(s.to_string())),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum Relief<W = u64> {
+    DivideBy(u64),
+    Modulo(W),
+    None,
+}
+
+impl<W: WorryLevel> Relief<W> {
+    pub fn apply(&self, worry_level: W) -> Result<W, ArithmeticError> {
+        match self {
+            Relief::DivideBy(divisor) => worry_level.checked_div(&W::from_u64(*divisor)),
+            Relief::Modulo(modulus) => worry_level.checked_rem(modulus),
+            Relief::None => Ok(worry_level),
+        }
+    }
+}
@@ -0,0 +1,280 @@
+use std::{
+    fmt::Display,
+    io::{self, Write},
+};
+
+use crate::{
+    parser::{Expression, Operator},
+    relief::Relief,
+    Monkey, Operation,
+};
+
+/// Something that happens while the monkeys play keep away.
+pub(crate) enum Event<'a, W> {
+    /// The monkey starts its turn.
+    Turn { monkey: usize },
+    /// The monkey inspects an item.
+    Inspection { worry_level: &'a W },
+    /// The monkey's operation produced a new worry level.
+    Operation {
+        operation: &'a Operation,
+        worry_level: &'a W,
+    },
+    /// The relief rule was applied after the inspection.
+    Relief {
+        relief: &'a Relief<W>,
+        worry_level: &'a W,
+    },
+    /// The monkey tested the worry level for divisibility.
+    Test { divisor: u64, divisible: bool },
+    /// The monkey threw the item.
+    Throw {
+        item: usize,
+        worry_level: &'a W,
+        target_monkey: usize,
+    },
+    /// All monkeys have had their turn. `round` counts from 1.
+    RoundEnd {
+        round: u64,
+        monkeys: &'a [Monkey<W>],
+    },
+}
+
+/// Receives every event of a simulation.
+pub(crate) trait Observer<W> {
+    fn observe(&mut self, event: &Event<'_, W>);
+}
+
+/// Ignores all events. The calls are inlined away, so a simulation without
+/// tracing runs as fast as one without the hook.
+pub(crate) struct NoTrace;
+
+impl<W> Observer<W> for NoTrace {
+    #[inline(always)]
+    fn observe(&mut self, _event: &Event<'_, W>) {}
+}
+
+/// Reports every event to both observers.
+impl<W, A: Observer<W>, B: Observer<W>> Observer<W> for (A, B) {
+    fn observe(&mut self, event: &Event<'_, W>) {
+        self.0.observe(event);
+        self.1.observe(event);
+    }
+}
+
+/// Reports events only if there is an observer.
+impl<W, O: Observer<W>> Observer<W> for Option<O> {
+    fn observe(&mut self, event: &Event<'_, W>) {
+        if let Some(observer) = self {
+            observer.observe(event);
+        }
+    }
+}
+
+/// Writes the events in the words of the puzzle description, so the output can
+/// be compared with the example walkthrough.
+pub(crate) struct TextTrace<Wr: Write> {
+    writer: Wr,
+    result: io::Result<()>,
+}
+
+impl<Wr: Write> TextTrace<Wr> {
+    pub fn new(writer: Wr) -> Self {
+        Self {
+            writer,
+            result: Ok(()),
+        }
+    }
+
+    /// Flushes the writer and returns the first error that occurred while
+    /// writing the trace.
+    pub fn finish(mut self) -> io::Result<()> {
+        self.result?;
+        self.writer.flush()
+    }
+
+    fn write_event<W: Display>(&mut self, event: &Event<'_, W>) -> io::Result<()> {
+        let writer = &mut self.writer;
+        match event {
+            Event::Turn { monkey } => writer.write_fmt(format_args!("Monkey {monkey}:\n")),
+            Event::Inspection { worry_level } => writer.write_fmt(format_args!(
+                "  Monkey inspects an item with a worry level of {worry_level}.\n"
+            )),
+            Event::Operation {
+                operation,
+                worry_level,
+            } => writer.write_fmt(format_args!(
+                "    Worry level {} to {worry_level}.\n",
+                describe_operation(&operation.expression)
+            )),
+            Event::Relief {
+                relief,
+                worry_level,
+            } => match relief {
+                Relief::DivideBy(divisor) => writer.write_fmt(format_args!(
+                    "    Monkey gets bored with item. Worry level is divided by {divisor} to {worry_level}.\n"
+                )),
+                Relief::Modulo(modulus) => writer.write_fmt(format_args!(
+                    "    Monkey gets bored with item. Worry level is reduced modulo {modulus} to {worry_level}.\n"
+                )),
+                Relief::None => Ok(()),
+            },
+            Event::Test { divisor, divisible } => writer.write_fmt(format_args!(
+                "    Current worry level is {}divisible by {divisor}.\n",
+                if *divisible { "" } else { "not " }
+            )),
+            Event::Throw {
+                worry_level,
+                target_monkey,
+                ..
+            } => writer.write_fmt(format_args!(
+                "    Item with worry level {worry_level} is thrown to monkey {target_monkey}.\n"
+            )),
+            Event::RoundEnd { round, monkeys } => {
+                writer.write_fmt(format_args!(
+                    "\nAfter round {round}, the monkeys are holding items with these worry levels:\n"
+                ))?;
+                for (i, monkey) in monkeys.iter().enumerate() {
+                    let worry_levels: Vec<_> = monkey
+                        .items
+                        .iter()
+                        .map(|item| format!("{}", item.worry_level))
+                        .collect();
+                    writer.write_fmt(format_args!("Monkey {i}: {}\n", worry_levels.join(", ")))?;
+                }
+                writer.write_all(b"\n")
+            }
+        }
+    }
+}
+
+impl<W: Display, Wr: Write> Observer<W> for TextTrace<Wr> {
+    fn observe(&mut self, event: &Event<'_, W>) {
+        if self.result.is_ok() {
+            self.result = self.write_event(event);
+        }
+    }
+}
+
+/// Describes an operation the way the puzzle does, e.g. "is multiplied by 19".
+/// Operations the puzzle never uses are written out.
+fn describe_operation(expression: &Expression) -> String {
+    if let Expression::Binary { operator, lhs, rhs } = expression {
+        match (operator, lhs.as_ref(), rhs.as_ref()) {
+            (Operator::Add, Expression::Old, Expression::Integer(value)) => {
+                return format!("increases by {value}")
+            }
+            (Operator::Multiply, Expression::Old, Expression::Integer(value)) => {
+                return format!("is multiplied by {value}")
+            }
+            (Operator::Add, Expression::Old, Expression::Old) => {
+                return "increases by itself".to_string()
+            }
+            (Operator::Multiply, Expression::Old, Expression::Old) => {
+                return "is multiplied by itself".to_string()
+            }
+            _ => {}
+        }
+    }
+    format!("is set to {expression}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TextTrace;
+    use crate::{notes, relief::Relief, simulate, tests::EXAMPLE};
+
+    /// The first round of the example walkthrough in the puzzle description.
+    const WALKTHROUGH: &str = "\
+Monkey 0:
+  Monkey inspects an item with a worry level of 79.
+    Worry level is multiplied by 19 to 1501.
+    Monkey gets bored with item. Worry level is divided by 3 to 500.
+    Current worry level is not divisible by 23.
+    Item with worry level 500 is thrown to monkey 3.
+  Monkey inspects an item with a worry level of 98.
+    Worry level is multiplied by 19 to 1862.
+    Monkey gets bored with item. Worry level is divided by 3 to 620.
+    Current worry level is not divisible by 23.
+    Item with worry level 620 is thrown to monkey 3.
+Monkey 1:
+  Monkey inspects an item with a worry level of 54.
+    Worry level increases by 6 to 60.
+    Monkey gets bored with item. Worry level is divided by 3 to 20.
+    Current worry level is not divisible by 19.
+    Item with worry level 20 is thrown to monkey 0.
+  Monkey inspects an item with a worry level of 65.
+    Worry level increases by 6 to 71.
+    Monkey gets bored with item. Worry level is divided by 3 to 23.
+    Current worry level is not divisible by 19.
+    Item with worry level 23 is thrown to monkey 0.
+  Monkey inspects an item with a worry level of 75.
+    Worry level increases by 6 to 81.
+    Monkey gets bored with item. Worry level is divided by 3 to 27.
+    Current worry level is not divisible by 19.
+    Item with worry level 27 is thrown to monkey 0.
+  Monkey inspects an item with a worry level of 74.
+    Worry level increases by 6 to 80.
+    Monkey gets bored with item. Worry level is divided by 3 to 26.
+    Current worry level is not divisible by 19.
+    Item with worry level 26 is thrown to monkey 0.
+Monkey 2:
+  Monkey inspects an item with a worry level of 79.
+    Worry level is multiplied by itself to 6241.
+    Monkey gets bored with item. Worry level is divided by 3 to 2080.
+    Current worry level is divisible by 13.
+    Item with worry level 2080 is thrown to monkey 1.
+  Monkey inspects an item with a worry level of 60.
+    Worry level is multiplied by itself to 3600.
+    Monkey gets bored with item. Worry level is divided by 3 to 1200.
+    Current worry level is not divisible by 13.
+    Item with worry level 1200 is thrown to monkey 3.
+  Monkey inspects an item with a worry level of 97.
+    Worry level is multiplied by itself to 9409.
+    Monkey gets bored with item. Worry level is divided by 3 to 3136.
+    Current worry level is not divisible by 13.
+    Item with worry level 3136 is thrown to monkey 3.
+Monkey 3:
+  Monkey inspects an item with a worry level of 74.
+    Worry level increases by 3 to 77.
+    Monkey gets bored with item. Worry level is divided by 3 to 25.
+    Current worry level is not divisible by 17.
+    Item with worry level 25 is thrown to monkey 1.
+  Monkey inspects an item with a worry level of 500.
+    Worry level increases by 3 to 503.
+    Monkey gets bored with item. Worry level is divided by 3 to 167.
+    Current worry level is not divisible by 17.
+    Item with worry level 167 is thrown to monkey 1.
+  Monkey inspects an item with a worry level of 620.
+    Worry level increases by 3 to 623.
+    Monkey gets bored with item. Worry level is divided by 3 to 207.
+    Current worry level is not divisible by 17.
+    Item with worry level 207 is thrown to monkey 1.
+  Monkey inspects an item with a worry level of 1200.
+    Worry level increases by 3 to 1203.
+    Monkey gets bored with item. Worry level is divided by 3 to 401.
+    Current worry level is not divisible by 17.
+    Item with worry level 401 is thrown to monkey 1.
+  Monkey inspects an item with a worry level of 3136.
+    Worry level increases by 3 to 3139.
+    Monkey gets bored with item. Worry level is divided by 3 to 1046.
+    Current worry level is not divisible by 17.
+    Item with worry level 1046 is thrown to monkey 1.
+
+After round 1, the monkeys are holding items with these worry levels:
+Monkey 0: 20, 23, 27, 26
+Monkey 1: 2080, 25, 167, 207, 401, 1046
+Monkey 2: 
+Monkey 3: 
+
+";
+
+    #[test]
+    fn first_round_of_the_walkthrough() {
+        let monkeys = notes::parse_input(EXAMPLE).unwrap();
+        let mut trace = TextTrace::new(Vec::new());
+        simulate::<u64>(monkeys, &Relief::DivideBy(3), 1, &mut trace).unwrap();
+        assert!(trace.result.is_ok());
+        assert_eq!(String::from_utf8(trace.writer).unwrap(), WALKTHROUGH);
+    }
+}
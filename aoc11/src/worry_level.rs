@@ -0,0 +1,154 @@
+use std::{error::Error, fmt::Display, str::FromStr};
+
+use crate::big_uint::BigUint;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum ArithmeticError {
+    Overflow,
+    /// A subtraction would have produced a negative worry level.
+    Underflow,
+    DivisionByZero,
+}
+
+impl Display for ArithmeticError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_fmt(format_args!("arithmetic error: {:?}", self))
+    }
+}
+
+impl Error for ArithmeticError {}
+
+/// A number type that worry levels can be stored in. All operations report
+/// errors instead of wrapping around or panicking.
+pub(crate) trait WorryLevel: Clone + Default + Display + PartialEq {
+    fn from_u64(value: u64) -> Self;
+    /// Returns the value if it fits into a `u64`.
+    fn to_u64(&self) -> Option<u64>;
+    fn is_zero(&self) -> bool;
+    fn checked_add(&self, rhs: &Self) -> Result<Self, ArithmeticError>;
+    fn checked_sub(&self, rhs: &Self) -> Result<Self, ArithmeticError>;
+    fn checked_mul(&self, rhs: &Self) -> Result<Self, ArithmeticError>;
+    fn checked_div(&self, rhs: &Self) -> Result<Self, ArithmeticError>;
+    fn checked_rem(&self, rhs: &Self) -> Result<Self, ArithmeticError>;
+
+    fn is_divisible_by(&self, divisor: u64) -> Result<bool, ArithmeticError> {
+        Ok(self.checked_rem(&Self::from_u64(divisor))?.is_zero())
+    }
+}
+
+macro_rules! impl_worry_level_for_primitive {
+    ($type:ty) => {
+        impl WorryLevel for $type {
+            fn from_u64(value: u64) -> Self {
+                value.into()
+            }
+
+            fn to_u64(&self) -> Option<u64> {
+                u64::try_from(*self).ok()
+            }
+
+            fn is_zero(&self) -> bool {
+                *self == 0
+            }
+
+            fn checked_add(&self, rhs: &Self) -> Result<Self, ArithmeticError> {
+                <$type>::checked_add(*self, *rhs).ok_or(ArithmeticError::Overflow)
+            }
+
+            fn checked_sub(&self, rhs: &Self) -> Result<Self, ArithmeticError> {
+                <$type>::checked_sub(*self, *rhs).ok_or(ArithmeticError::Underflow)
+            }
+
+            fn checked_mul(&self, rhs: &Self) -> Result<Self, ArithmeticError> {
+                <$type>::checked_mul(*self, *rhs).ok_or(ArithmeticError::Overflow)
+            }
+
+            fn checked_div(&self, rhs: &Self) -> Result<Self, ArithmeticError> {
+                <$type>::checked_div(*self, *rhs).ok_or(ArithmeticError::DivisionByZero)
+            }
+
+            fn checked_rem(&self, rhs: &Self) -> Result<Self, ArithmeticError> {
+                <$type>::checked_rem(*self, *rhs).ok_or(ArithmeticError::DivisionByZero)
+            }
+        }
+    };
+}
+
+impl_worry_level_for_primitive!(u64);
+impl_worry_level_for_primitive!(u128);
+
+impl WorryLevel for BigUint {
+    fn from_u64(value: u64) -> Self {
+        BigUint::from(value)
+    }
+
+    fn to_u64(&self) -> Option<u64> {
+        BigUint::to_u64(self)
+    }
+
+    fn is_zero(&self) -> bool {
+        BigUint::is_zero(self)
+    }
+
+    fn checked_add(&self, rhs: &Self) -> Result<Self, ArithmeticError> {
+        Ok(self.add(rhs))
+    }
+
+    fn checked_sub(&self, rhs: &Self) -> Result<Self, ArithmeticError> {
+        self.sub(rhs).ok_or(ArithmeticError::Underflow)
+    }
+
+    fn checked_mul(&self, rhs: &Self) -> Result<Self, ArithmeticError> {
+        Ok(self.mul(rhs))
+    }
+
+    fn checked_div(&self, rhs: &Self) -> Result<Self, ArithmeticError> {
+        self.div_rem(rhs)
+            .map(|(quotient, _)| quotient)
+            .ok_or(ArithmeticError::DivisionByZero)
+    }
+
+    fn checked_rem(&self, rhs: &Self) -> Result<Self, ArithmeticError> {
+        self.div_rem(rhs)
+            .map(|(_, remainder)| remainder)
+            .ok_or(ArithmeticError::DivisionByZero)
+    }
+}
+
+/// The number type used for a run of the simulation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum WorryLevelMode {
+    /// `u64`, failing on overflow.
+    CheckedU64,
+    /// `u128`, failing on overflow.
+    U128,
+    /// Arbitrary precision, never overflows.
+    BigInteger,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct UnknownWorryLevelMode(String);
+
+impl Display for UnknownWorryLevelMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_fmt(format_args!(
+            "unknown worry level mode \"{}\" (expected \"u64\", \"u128\" or \"big\")",
+            self.0
+        ))
+    }
+}
+
+impl Error for UnknownWorryLevelMode {}
+
+impl FromStr for WorryLevelMode {
+    type Err = UnknownWorryLevelMode;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "u64" => Ok(WorryLevelMode::CheckedU64),
+            "u128" => Ok(WorryLevelMode::U128),
+            "big" => Ok(WorryLevelMode::BigInteger),
+            _ => Err(UnknownWorryLevelMode(s.to_string())),
+        }
+    }
+}